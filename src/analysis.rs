@@ -0,0 +1,693 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::iter::FromIterator;
+
+use crate::card::Card;
+use crate::deck::DECK_52;
+use crate::game::Decision;
+use crate::hand::Hand;
+use crate::rules::{DoubleDownRule, HoleCardRule, Rules, SurrenderRule};
+use crate::score::BlackjackScore;
+use crate::strategy::initially_legal;
+
+// The cards left in a shoe, counted by their value with aces as 1.
+#[derive(
+	Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+pub struct Composition {
+	counts: [u32; 10],
+}
+
+impl Composition {
+	pub fn decks(decks: usize) -> Self {
+		(0..decks).flat_map(|_| DECK_52.cards().copied()).collect()
+	}
+
+	// Number of cards left with `value`, 1 for aces up to 10.
+	pub fn count(&self, value: u8) -> u32 {
+		match value {
+			1..=10 => self.counts[usize::from(value - 1)],
+			_ => 0,
+		}
+	}
+
+	pub fn len(&self) -> u32 {
+		self.counts.iter().sum()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	pub const fn add(&mut self, card: &Card) {
+		self.counts[Self::index(card)] += 1;
+	}
+
+	// Takes `card` out of the shoe, false if there is none left.
+	pub const fn remove(&mut self, card: &Card) -> bool {
+		let count = &mut self.counts[Self::index(card)];
+		if *count == 0 {
+			return false;
+		}
+		*count -= 1;
+		true
+	}
+
+	const fn index(card: &Card) -> usize {
+		BlackjackScore::value(card.rank()) as usize - 1
+	}
+
+	// The values which can be drawn next with their probabilities.
+	fn draws(&self) -> impl Iterator<Item = (u8, f64)> + '_ {
+		let len = f64::from(self.len());
+		(1..=10)
+			.zip(&self.counts)
+			.filter(|(_, &count)| count > 0)
+			.map(move |(value, &count)| (value, f64::from(count) / len))
+	}
+
+	const fn take(&mut self, value: u8) {
+		self.counts[value as usize - 1] -= 1;
+	}
+
+	const fn put(&mut self, value: u8) {
+		self.counts[value as usize - 1] += 1;
+	}
+}
+
+impl FromIterator<Card> for Composition {
+	fn from_iter<I: IntoIterator<Item = Card>>(iter: I) -> Self {
+		let mut composition = Self::default();
+		for card in iter {
+			composition.add(&card);
+		}
+		composition
+	}
+}
+
+// The hard total of a hand and whether it holds an ace, from which the soft
+// total follows like in `BlackjackScore`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct Total {
+	hard: u8,
+	ace: bool,
+	cards: u8,
+}
+
+impl Total {
+	const EMPTY: Self = Self { hard: 0, ace: false, cards: 0 };
+
+	const fn new(value: u8) -> Self {
+		Self::EMPTY.add(value)
+	}
+
+	const fn add(self, value: u8) -> Self {
+		Self {
+			hard: self.hard + value,
+			ace: self.ace || value == 1,
+			cards: self.cards + 1,
+		}
+	}
+
+	const fn soft(self) -> Option<u8> {
+		if self.ace && self.hard <= 11 {
+			Some(self.hard + 10)
+		} else {
+			None
+		}
+	}
+
+	const fn best(self) -> u8 {
+		match self.soft() {
+			Some(soft) => soft,
+			None => self.hard,
+		}
+	}
+}
+
+// How the dealer finishes: on a total from 17 to 21, with a blackjack or bust.
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd)]
+pub struct DealerProbabilities {
+	pub totals: [f64; 5],
+	pub blackjack: f64,
+	pub bust: f64,
+}
+
+impl DealerProbabilities {
+	// Exact probabilities for the dealer showing `upcard`, drawing from the
+	// cards in `shoe` which must not include the upcard anymore.
+	pub fn new(upcard: &Card, shoe: &Composition, rules: &Rules) -> Self {
+		Self::with_upcard(BlackjackScore::value(upcard.rank()), shoe, rules)
+	}
+
+	fn with_upcard(up: u8, shoe: &Composition, rules: &Rules) -> Self {
+		let mut probabilities = Self::default();
+		let mut shoe = *shoe;
+		probabilities.play(
+			Total::new(up),
+			&mut shoe,
+			rules.dealer_hits_soft_17,
+			1.0,
+		);
+		probabilities
+	}
+
+	fn play(
+		&mut self,
+		total: Total,
+		shoe: &mut Composition,
+		hits_soft_17: bool,
+		probability: f64,
+	) {
+		let best = total.best();
+
+		if total.hard > 21 {
+			self.bust += probability;
+			return;
+		}
+		if total.cards == 2 && best == 21 {
+			self.blackjack += probability;
+			return;
+		}
+		if best >= 17
+			&& !(best == 17 && total.soft().is_some() && hits_soft_17)
+		{
+			self.totals[usize::from(best - 17)] += probability;
+			return;
+		}
+		if shoe.is_empty() {
+			// an exhausted shoe would stop any game, the hand is ignored
+			return;
+		}
+
+		for (value, draw) in shoe.draws().collect::<Vec<_>>() {
+			shoe.take(value);
+			self.play(
+				total.add(value),
+				shoe,
+				hits_soft_17,
+				probability * draw,
+			);
+			shoe.put(value);
+		}
+	}
+
+	// The probability of the dealer finishing on `total` from 17 to 21.
+	pub fn total(&self, total: u8) -> f64 {
+		match total {
+			17..=21 => self.totals[usize::from(total - 17)],
+			_ => 0.0,
+		}
+	}
+
+	// The probabilities once the dealer peeked and has no blackjack.
+	pub fn without_blackjack(&self) -> Self {
+		let remaining = 1.0 - self.blackjack;
+		if remaining <= 0.0 {
+			return Self::default();
+		}
+
+		let mut totals = self.totals;
+		for total in &mut totals {
+			*total /= remaining;
+		}
+		Self { totals, blackjack: 0.0, bust: self.bust / remaining }
+	}
+}
+
+impl fmt::Display for DealerProbabilities {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		for probability in &self.totals {
+			write!(f, "{:>8.4}%", probability * 100.0)?;
+		}
+		write!(
+			f,
+			"{:>8.4}%{:>8.4}%",
+			self.blackjack * 100.0,
+			self.bust * 100.0
+		)
+	}
+}
+
+// Exact expected values of the decisions for a hand against the dealer
+// upcard, in units of the initial bet. Like the decisions themselves they
+// assume the dealer already peeked for a blackjack, unless surrendering
+// comes before the peek. Draws of the player are taken from the shoe as if
+// the hole card was not, and split hands are valued as two hands played
+// independently without resplitting.
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd)]
+pub struct ExpectedValues {
+	pub hit: f64,
+	pub stand: f64,
+	pub double_down: Option<f64>,
+	pub split: Option<f64>,
+	pub surrender: Option<f64>,
+}
+
+impl ExpectedValues {
+	// `shoe` holds the unseen cards, without the hand and the upcard.
+	pub fn new(
+		hand: &Hand,
+		upcard: &Card,
+		shoe: &Composition,
+		rules: &Rules,
+	) -> Self {
+		let up = BlackjackScore::value(upcard.rank());
+		let peek = rules.hole_card == HoleCardRule::Peek;
+		let mut solver = Solver {
+			up,
+			rules,
+			peek,
+			dealer: HashMap::new(),
+			hands: HashMap::new(),
+		};
+
+		let total = hand.cards().iter().fold(Total::EMPTY, |total, card| {
+			total.add(BlackjackScore::value(card.rank()))
+		});
+		let natural = total.cards == 2 && total.best() == 21;
+
+		let stand = if natural {
+			let payout = rules.blackjack_payout;
+			let dealer = solver.dealer(shoe);
+			f64::from(payout.win) / f64::from(payout.bet)
+				* (1.0 - dealer.blackjack)
+		} else {
+			solver.stand(total, shoe, 1.0, solver.blackjack_loss(1.0, 1))
+		};
+
+		let mut values = Self {
+			hit: solver.hit(total, shoe, 1),
+			stand,
+			double_down: None,
+			split: None,
+			surrender: None,
+		};
+
+		if initially_legal(hand, rules, Decision::DoubleDown) {
+			let loss = solver.blackjack_loss(2.0, 1);
+			values.double_down = Some(solver.double(total, shoe, loss));
+		}
+		if let (true, [card, _]) = (
+			initially_legal(hand, rules, Decision::Split)
+				&& rules.max_split_hands >= 2,
+			hand.cards(),
+		) {
+			let value = BlackjackScore::value(card.rank());
+			values.split = Some(2.0 * solver.split(value, shoe));
+		}
+
+		// an early surrender comes before the peek, so the other decisions
+		// are compared with the original bet still at risk to a blackjack
+		if peek
+			&& rules.surrender == SurrenderRule::Early
+			&& (up == 1 || up == 10)
+		{
+			let blackjack =
+				DealerProbabilities::new(upcard, shoe, rules).blackjack;
+			let before_peek =
+				|value: f64| (1.0 - blackjack) * value - blackjack;
+
+			values.hit = before_peek(values.hit);
+			// a natural pushes against the dealer blackjack
+			values.stand = if natural {
+				(1.0 - blackjack) * values.stand
+			} else {
+				before_peek(values.stand)
+			};
+			values.double_down = values.double_down.map(before_peek);
+			values.split = values.split.map(before_peek);
+		}
+		if initially_legal(hand, rules, Decision::Surrender) {
+			values.surrender = Some(-0.5);
+		}
+
+		values
+	}
+
+	pub const fn value(&self, decision: Decision) -> Option<f64> {
+		match decision {
+			Decision::Hit => Some(self.hit),
+			Decision::Stand => Some(self.stand),
+			Decision::DoubleDown => self.double_down,
+			Decision::Split => self.split,
+			Decision::Surrender => self.surrender,
+		}
+	}
+
+	// The decision with the highest expected value.
+	pub fn best(&self) -> (Decision, f64) {
+		DECISIONS
+			.iter()
+			.filter_map(|&decision| {
+				self.value(decision).map(|value| (decision, value))
+			})
+			.fold((Decision::Stand, self.stand), |best, next| {
+				if next.1 > best.1 {
+					next
+				} else {
+					best
+				}
+			})
+	}
+}
+
+const DECISIONS: [Decision; 5] = [
+	Decision::Hit,
+	Decision::Stand,
+	Decision::DoubleDown,
+	Decision::Split,
+	Decision::Surrender,
+];
+
+impl fmt::Display for ExpectedValues {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let (best, _) = self.best();
+
+		for decision in &DECISIONS {
+			let name = decision.to_string();
+			match self.value(*decision) {
+				Some(value) => writeln!(
+					f,
+					"{:<11}{:>+10.4}%{}",
+					name,
+					value * 100.0,
+					if *decision == best { "  <- best" } else { "" }
+				)?,
+				None => writeln!(f, "{:<11}{:>11}", name, "-")?,
+			}
+		}
+
+		Ok(())
+	}
+}
+
+// Memoizes the dealer outcomes of every shoe the player can draw to and the
+// values of the hands played on.
+struct Solver<'r> {
+	up: u8,
+	rules: &'r Rules,
+	// decisions are made knowing the dealer has no blackjack
+	peek: bool,
+	dealer: HashMap<Composition, DealerProbabilities>,
+	hands: HashMap<(Composition, u8, bool, u8), f64>,
+}
+
+impl Solver<'_> {
+	fn dealer(&mut self, shoe: &Composition) -> DealerProbabilities {
+		if let Some(dealer) = self.dealer.get(shoe) {
+			return *dealer;
+		}
+
+		let mut dealer =
+			DealerProbabilities::with_upcard(self.up, shoe, self.rules);
+		if self.peek {
+			dealer = dealer.without_blackjack();
+		}
+
+		self.dealer.insert(*shoe, dealer);
+		dealer
+	}
+
+	// What a dealer blackjack takes from a hand with `wager` out of `hands`
+	// hands of the player.
+	fn blackjack_loss(&self, wager: f64, hands: u8) -> f64 {
+		match self.rules.hole_card {
+			HoleCardRule::NoHoleCard { original_bets_only: false } => wager,
+			_ => 1.0 / f64::from(hands),
+		}
+	}
+
+	fn stand(
+		&mut self,
+		total: Total,
+		shoe: &Composition,
+		wager: f64,
+		blackjack_loss: f64,
+	) -> f64 {
+		if total.hard > 21 {
+			return -wager;
+		}
+
+		let dealer = self.dealer(shoe);
+		let best = total.best();
+		let mut value =
+			dealer.bust * wager - dealer.blackjack * blackjack_loss;
+		for (dealer_total, probability) in (17..=21).zip(&dealer.totals) {
+			if best > dealer_total {
+				value += probability * wager;
+			} else if best < dealer_total {
+				value -= probability * wager;
+			}
+		}
+
+		value
+	}
+
+	fn hit(&mut self, total: Total, shoe: &Composition, hands: u8) -> f64 {
+		let mut shoe = *shoe;
+		let mut value = 0.0;
+
+		for (card, probability) in shoe.draws().collect::<Vec<_>>() {
+			let next = total.add(card);
+			shoe.take(card);
+			value += probability
+				* if next.hard > 21 {
+					-1.0
+				} else {
+					self.play(next, &shoe, hands)
+				};
+			shoe.put(card);
+		}
+
+		value
+	}
+
+	// The value of standing or hitting on, whichever is better.
+	fn play(&mut self, total: Total, shoe: &Composition, hands: u8) -> f64 {
+		let key = (*shoe, total.hard, total.ace, hands);
+		if let Some(value) = self.hands.get(&key) {
+			return *value;
+		}
+
+		let loss = self.blackjack_loss(1.0, hands);
+		let value = self
+			.stand(total, shoe, 1.0, loss)
+			.max(self.hit(total, shoe, hands));
+
+		self.hands.insert(key, value);
+		value
+	}
+
+	fn double(
+		&mut self,
+		total: Total,
+		shoe: &Composition,
+		blackjack_loss: f64,
+	) -> f64 {
+		let mut shoe = *shoe;
+		let mut value = 0.0;
+
+		for (card, probability) in shoe.draws().collect::<Vec<_>>() {
+			shoe.take(card);
+			value += probability
+				* self.stand(total.add(card), &shoe, 2.0, blackjack_loss);
+			shoe.put(card);
+		}
+
+		value
+	}
+
+	// The value of one of the two hands split from a pair of `card`.
+	fn split(&mut self, card: u8, shoe: &Composition) -> f64 {
+		let rules = self.rules;
+		let aces = card == 1;
+		let mut shoe = *shoe;
+		let mut value = 0.0;
+
+		for (second, probability) in shoe.draws().collect::<Vec<_>>() {
+			let total = Total::new(card).add(second);
+			shoe.take(second);
+
+			// split aces take only one card unless they may be hit
+			let hand = if aces && !rules.hit_split_aces {
+				let loss = self.blackjack_loss(1.0, 2);
+				self.stand(total, &shoe, 1.0, loss)
+			} else if rules.double_after_split
+				&& can_double(total, rules.double_down)
+			{
+				let loss = self.blackjack_loss(2.0, 2);
+				self.play(total, &shoe, 2).max(self.double(total, &shoe, loss))
+			} else {
+				self.play(total, &shoe, 2)
+			};
+
+			value += probability * hand;
+			shoe.put(second);
+		}
+
+		value
+	}
+}
+
+const fn can_double(total: Total, rule: DoubleDownRule) -> bool {
+	let hard = total.soft().is_none();
+	match rule {
+		DoubleDownRule::Any => true,
+		DoubleDownRule::NineToEleven => {
+			hard && total.hard >= 9 && total.hard <= 11
+		}
+		DoubleDownRule::TenToEleven => {
+			hard && total.hard >= 10 && total.hard <= 11
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const UPCARDS: [Card; 10] = [
+		Card::SPADE_TWO,
+		Card::SPADE_THREE,
+		Card::SPADE_FOUR,
+		Card::SPADE_FIVE,
+		Card::SPADE_SIX,
+		Card::SPADE_SEVEN,
+		Card::SPADE_EIGHT,
+		Card::SPADE_NINE,
+		Card::SPADE_TEN,
+		Card::SPADE_ACE,
+	];
+
+	// Published infinite deck probabilities of the dealer finishing on 17 to
+	// 21, with a blackjack or bust for the upcards 2 to 10 and ace, before
+	// the peek.
+	const S17: [[f64; 7]; 10] = [
+		[0.139809, 0.134907, 0.129655, 0.124026, 0.117993, 0.0, 0.353608],
+		[0.135034, 0.130482, 0.125581, 0.120329, 0.114700, 0.0, 0.373875],
+		[0.130490, 0.125938, 0.121386, 0.116485, 0.111233, 0.0, 0.394468],
+		[0.122251, 0.122251, 0.117700, 0.113148, 0.108246, 0.0, 0.416404],
+		[0.165438, 0.106267, 0.106267, 0.101715, 0.097163, 0.0, 0.423150],
+		[0.368566, 0.137797, 0.078625, 0.078625, 0.074074, 0.0, 0.262312],
+		[0.128567, 0.359336, 0.128567, 0.069395, 0.069395, 0.0, 0.244741],
+		[0.119995, 0.119995, 0.350765, 0.119995, 0.060824, 0.0, 0.228425],
+		[0.111424, 0.111424, 0.111424, 0.342194, 0.034501, 0.076923, 0.212109],
+		[0.130789, 0.130789, 0.130789, 0.130789, 0.053866, 0.307692, 0.115286],
+	];
+
+	// Only the dealer showing 2 to 6 or an ace can stand on a soft 17.
+	const H17: [[f64; 7]; 10] = [
+		[0.130134, 0.136546, 0.131294, 0.125665, 0.119632, 0.0, 0.356728],
+		[0.126328, 0.131957, 0.127055, 0.121803, 0.116174, 0.0, 0.376682],
+		[0.122406, 0.127307, 0.122756, 0.117854, 0.112602, 0.0, 0.397075],
+		[0.118359, 0.122911, 0.118359, 0.113807, 0.108905, 0.0, 0.417659],
+		[0.114838, 0.114838, 0.114838, 0.110286, 0.105734, 0.0, 0.439467],
+		S17[5],
+		S17[6],
+		S17[7],
+		S17[8],
+		[0.057493, 0.143204, 0.143204, 0.143204, 0.066281, 0.307692, 0.138920],
+	];
+
+	// A shoe big enough to draw from like an infinite deck.
+	fn dealer(upcard: &Card, rules: &Rules) -> DealerProbabilities {
+		let mut shoe = Composition::decks(20_000);
+		shoe.remove(upcard);
+		DealerProbabilities::new(upcard, &shoe, rules)
+	}
+
+	fn assert_close(actual: f64, expected: f64, what: &str) {
+		assert!(
+			(actual - expected).abs() < 5e-5,
+			"{}: {} instead of {}",
+			what,
+			actual,
+			expected
+		);
+	}
+
+	// Compares every upcard and the bust probability over all of them, a
+	// ten valued upcard being four times as likely.
+	fn assert_published(rules: &Rules, published: &[[f64; 7]; 10], bust: f64) {
+		let mut overall = 0.0;
+		for (upcard, expected) in UPCARDS.iter().zip(published) {
+			let dealer = dealer(upcard, rules);
+			for total in 17..=21 {
+				assert_close(
+					dealer.total(total),
+					expected[usize::from(total - 17)],
+					&format!("{} on {}", upcard, total),
+				);
+			}
+			assert_close(dealer.blackjack, expected[5], &upcard.to_string());
+			assert_close(dealer.bust, expected[6], &upcard.to_string());
+
+			let sum = dealer.totals.iter().sum::<f64>()
+				+ dealer.blackjack
+				+ dealer.bust;
+			assert_close(sum, 1.0, &upcard.to_string());
+			overall += dealer.bust
+				* if *upcard == Card::SPADE_TEN { 4.0 } else { 1.0 }
+				/ 13.0;
+		}
+		assert_close(overall, bust, "all upcards");
+	}
+
+	#[test]
+	fn dealer_standing_on_soft_17_matches_published_probabilities() {
+		assert_published(&Rules::default(), &S17, 0.281593);
+	}
+
+	#[test]
+	fn dealer_hitting_soft_17_matches_published_probabilities() {
+		let rules = Rules { dealer_hits_soft_17: true, ..Rules::default() };
+		assert_published(&rules, &H17, 0.285419);
+	}
+
+	#[test]
+	fn peeking_leaves_the_hands_without_blackjack() {
+		let ace = dealer(&Card::SPADE_ACE, &Rules::default());
+		let peeked = ace.without_blackjack();
+		assert_eq!(peeked.blackjack, 0.0);
+		assert_close(peeked.bust, 0.115286 / (1.0 - 0.307692), "ace");
+		assert_close(peeked.total(17), 0.130789 / (1.0 - 0.307692), "ace");
+	}
+
+	// Values of an infinite deck, for hands drawn from the huge shoe.
+	fn expected(hand: &str, upcard: &Card) -> ExpectedValues {
+		let hand = hand.parse::<Hand>().unwrap();
+		let mut shoe = Composition::decks(20_000);
+		for card in hand.cards().iter().chain(Some(upcard)) {
+			shoe.remove(card);
+		}
+		ExpectedValues::new(&hand, upcard, &shoe, &Rules::default())
+	}
+
+	#[test]
+	fn stiff_hands_match_published_values() {
+		let values = expected("Th 6c", &Card::SPADE_TEN);
+		assert_close(values.stand, -0.540430, "stand");
+		assert_close(values.hit, -0.539826, "hit");
+		assert_close(values.surrender.unwrap(), -0.5, "surrender");
+
+		let values = expected("Th 6c", &Card::SPADE_SIX);
+		assert_close(values.stand, -0.153699, "stand");
+	}
+
+	#[test]
+	fn doubling_11_against_6_matches_published_values() {
+		let values = expected("5h 6c", &Card::SPADE_SIX);
+		assert_close(values.double_down.unwrap(), 0.667380, "double");
+		assert_close(values.hit, 0.333690, "hit");
+		assert_close(values.stand, -0.153699, "stand");
+		assert_eq!(values.split, None);
+	}
+
+	// Each ace draws to the same totals as a doubled 11, for half the bet.
+	#[test]
+	fn split_aces_take_a_card_each() {
+		let values = expected("Ah Ac", &Card::SPADE_SIX);
+		assert_close(values.split.unwrap(), 0.667380, "split");
+	}
+}
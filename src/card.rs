@@ -0,0 +1,384 @@
+use std::fmt::{self, Write as _};
+use std::str::FromStr;
+
+#[cfg(feature = "serde")]
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Suit {
+	Club,
+	Diamond,
+	Heart,
+	Spade,
+}
+
+impl Suit {
+	const ALL: [Self; 4] =
+		[Self::Club, Self::Diamond, Self::Heart, Self::Spade];
+}
+
+impl fmt::Display for Suit {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Club => f.write_str("♣"),
+			Self::Diamond => f.write_str("♦"),
+			Self::Heart => f.write_str("♥"),
+			Self::Spade => f.write_str("♠"),
+		}
+	}
+}
+
+impl FromStr for Suit {
+	type Err = ParseCardError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s.trim() {
+			"c" | "C" | "♣" | "♧" => Ok(Self::Club),
+			"d" | "D" | "♦" | "♢" => Ok(Self::Diamond),
+			"h" | "H" | "♥" | "♡" => Ok(Self::Heart),
+			"s" | "S" | "♠" | "♤" => Ok(Self::Spade),
+			"" => Err(ParseCardError::Empty),
+			suit => Err(ParseCardError::InvalidSuit(suit.to_owned())),
+		}
+	}
+}
+
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Rank {
+	Ace,
+	Two,
+	Three,
+	Four,
+	Five,
+	Six,
+	Seven,
+	Eight,
+	Nine,
+	Ten,
+	Jack,
+	Queen,
+	King,
+}
+
+impl Rank {
+	const ALL: [Self; 13] = [
+		Self::Ace,
+		Self::Two,
+		Self::Three,
+		Self::Four,
+		Self::Five,
+		Self::Six,
+		Self::Seven,
+		Self::Eight,
+		Self::Nine,
+		Self::Ten,
+		Self::Jack,
+		Self::Queen,
+		Self::King,
+	];
+}
+
+impl fmt::Display for Rank {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Ace => f.write_str("A"),
+			Self::Two => f.write_str("2"),
+			Self::Three => f.write_str("3"),
+			Self::Four => f.write_str("4"),
+			Self::Five => f.write_str("5"),
+			Self::Six => f.write_str("6"),
+			Self::Seven => f.write_str("7"),
+			Self::Eight => f.write_str("8"),
+			Self::Nine => f.write_str("9"),
+			Self::Ten => f.write_str("10"),
+			Self::Jack => f.write_str("J"),
+			Self::Queen => f.write_str("Q"),
+			Self::King => f.write_str("K"),
+		}
+	}
+}
+
+impl FromStr for Rank {
+	type Err = ParseCardError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let rank = match s.trim() {
+			"a" | "A" => Self::Ace,
+			"2" => Self::Two,
+			"3" => Self::Three,
+			"4" => Self::Four,
+			"5" => Self::Five,
+			"6" => Self::Six,
+			"7" => Self::Seven,
+			"8" => Self::Eight,
+			"9" => Self::Nine,
+			"10" | "t" | "T" => Self::Ten,
+			"j" | "J" => Self::Jack,
+			"q" | "Q" => Self::Queen,
+			"k" | "K" => Self::King,
+			"" => return Err(ParseCardError::Empty),
+			rank => return Err(ParseCardError::InvalidRank(rank.to_owned())),
+		};
+
+		Ok(rank)
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Card(Suit, Rank);
+
+impl Card {
+	pub const CLUB_ACE: Self = Self(Suit::Club, Rank::Ace);
+	pub const CLUB_EIGHT: Self = Self(Suit::Club, Rank::Eight);
+	pub const CLUB_FIVE: Self = Self(Suit::Club, Rank::Five);
+	pub const CLUB_FOUR: Self = Self(Suit::Club, Rank::Four);
+	pub const CLUB_JACK: Self = Self(Suit::Club, Rank::Jack);
+	pub const CLUB_KING: Self = Self(Suit::Club, Rank::King);
+	pub const CLUB_NINE: Self = Self(Suit::Club, Rank::Nine);
+	pub const CLUB_QUEEN: Self = Self(Suit::Club, Rank::Queen);
+	pub const CLUB_SEVEN: Self = Self(Suit::Club, Rank::Seven);
+	pub const CLUB_SIX: Self = Self(Suit::Club, Rank::Six);
+	pub const CLUB_TEN: Self = Self(Suit::Club, Rank::Ten);
+	pub const CLUB_THREE: Self = Self(Suit::Club, Rank::Three);
+	pub const CLUB_TWO: Self = Self(Suit::Club, Rank::Two);
+	pub const DIAMOND_ACE: Self = Self(Suit::Diamond, Rank::Ace);
+	pub const DIAMOND_EIGHT: Self = Self(Suit::Diamond, Rank::Eight);
+	pub const DIAMOND_FIVE: Self = Self(Suit::Diamond, Rank::Five);
+	pub const DIAMOND_FOUR: Self = Self(Suit::Diamond, Rank::Four);
+	pub const DIAMOND_JACK: Self = Self(Suit::Diamond, Rank::Jack);
+	pub const DIAMOND_KING: Self = Self(Suit::Diamond, Rank::King);
+	pub const DIAMOND_NINE: Self = Self(Suit::Diamond, Rank::Nine);
+	pub const DIAMOND_QUEEN: Self = Self(Suit::Diamond, Rank::Queen);
+	pub const DIAMOND_SEVEN: Self = Self(Suit::Diamond, Rank::Seven);
+	pub const DIAMOND_SIX: Self = Self(Suit::Diamond, Rank::Six);
+	pub const DIAMOND_TEN: Self = Self(Suit::Diamond, Rank::Ten);
+	pub const DIAMOND_THREE: Self = Self(Suit::Diamond, Rank::Three);
+	pub const DIAMOND_TWO: Self = Self(Suit::Diamond, Rank::Two);
+	pub const HEART_ACE: Self = Self(Suit::Heart, Rank::Ace);
+	pub const HEART_EIGHT: Self = Self(Suit::Heart, Rank::Eight);
+	pub const HEART_FIVE: Self = Self(Suit::Heart, Rank::Five);
+	pub const HEART_FOUR: Self = Self(Suit::Heart, Rank::Four);
+	pub const HEART_JACK: Self = Self(Suit::Heart, Rank::Jack);
+	pub const HEART_KING: Self = Self(Suit::Heart, Rank::King);
+	pub const HEART_NINE: Self = Self(Suit::Heart, Rank::Nine);
+	pub const HEART_QUEEN: Self = Self(Suit::Heart, Rank::Queen);
+	pub const HEART_SEVEN: Self = Self(Suit::Heart, Rank::Seven);
+	pub const HEART_SIX: Self = Self(Suit::Heart, Rank::Six);
+	pub const HEART_TEN: Self = Self(Suit::Heart, Rank::Ten);
+	pub const HEART_THREE: Self = Self(Suit::Heart, Rank::Three);
+	pub const HEART_TWO: Self = Self(Suit::Heart, Rank::Two);
+	pub const SPADE_ACE: Self = Self(Suit::Spade, Rank::Ace);
+	pub const SPADE_EIGHT: Self = Self(Suit::Spade, Rank::Eight);
+	pub const SPADE_FIVE: Self = Self(Suit::Spade, Rank::Five);
+	pub const SPADE_FOUR: Self = Self(Suit::Spade, Rank::Four);
+	pub const SPADE_JACK: Self = Self(Suit::Spade, Rank::Jack);
+	pub const SPADE_KING: Self = Self(Suit::Spade, Rank::King);
+	pub const SPADE_NINE: Self = Self(Suit::Spade, Rank::Nine);
+	pub const SPADE_QUEEN: Self = Self(Suit::Spade, Rank::Queen);
+	pub const SPADE_SEVEN: Self = Self(Suit::Spade, Rank::Seven);
+	pub const SPADE_SIX: Self = Self(Suit::Spade, Rank::Six);
+	pub const SPADE_TEN: Self = Self(Suit::Spade, Rank::Ten);
+	pub const SPADE_THREE: Self = Self(Suit::Spade, Rank::Three);
+	pub const SPADE_TWO: Self = Self(Suit::Spade, Rank::Two);
+
+	pub const fn new(suit: Suit, rank: Rank) -> Self {
+		Self(suit, rank)
+	}
+
+	pub const fn suit(&self) -> &Suit {
+		&self.0
+	}
+
+	pub const fn rank(&self) -> &Rank {
+		&self.1
+	}
+}
+
+impl fmt::Display for Card {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let base = match self.suit() {
+			Suit::Club => '\u{1F0A1}',
+			Suit::Diamond => '\u{1F0B1}',
+			Suit::Heart => '\u{1F0C1}',
+			Suit::Spade => '\u{1F0D1}',
+		};
+
+		let mut add = *self.rank() as u8 as u32;
+		if self.rank() > &Rank::Jack {
+			add += 1;
+		}
+
+		match char::from_u32(base as u32 + add) {
+			Some(glyph) => f.write_char(glyph),
+			None => write!(f, "{}{}", self.rank(), self.suit()),
+		}
+	}
+}
+
+// Accepts the rank followed by the suit (`AS`, `10h`, `Td`, `K♠`) or one of
+// the playing card glyphs used by `Display`.
+impl FromStr for Card {
+	type Err = ParseCardError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let s = s.trim();
+		let mut chars = s.chars();
+
+		match (chars.next(), chars.next()) {
+			(None, _) => Err(ParseCardError::Empty),
+			(Some(glyph), None) if !glyph.is_ascii() => {
+				Self::from_glyph(glyph)
+			}
+			(Some(_), None) => Err(ParseCardError::MissingSuit(s.to_owned())),
+			_ => {
+				// the suit is always the last character, everything before it
+				// is the rank
+				let split = s.char_indices().last().map_or(0, |(idx, _)| idx);
+				let (rank, suit) = s.split_at(split);
+				Ok(Self(suit.parse()?, rank.parse()?))
+			}
+		}
+	}
+}
+
+// Cards are written as their rank and suit, like `10♥`, which `str::parse`
+// reads back.
+#[cfg(feature = "serde")]
+impl Serialize for Card {
+	fn serialize<S: Serializer>(
+		&self,
+		serializer: S,
+	) -> Result<S::Ok, S::Error> {
+		serializer.collect_str(&format_args!("{}{}", self.rank(), self.suit()))
+	}
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for Card {
+	fn deserialize<D: Deserializer<'de>>(
+		deserializer: D,
+	) -> Result<Self, D::Error> {
+		let card = String::deserialize(deserializer)?;
+		card.parse().map_err(de::Error::custom)
+	}
+}
+
+impl Card {
+	fn from_glyph(glyph: char) -> Result<Self, ParseCardError> {
+		let code = u32::from(glyph);
+		let (suit, offset) = match code.checked_sub(0x1F0A1) {
+			Some(offset) if offset < 0x40 => (offset / 0x10, offset % 0x10),
+			_ => return Err(ParseCardError::InvalidGlyph(glyph)),
+		};
+
+		// the knight sits between the jack and the queen
+		let rank = match offset {
+			0..=10 => offset,
+			12..=13 => offset - 1,
+			_ => return Err(ParseCardError::InvalidGlyph(glyph)),
+		};
+
+		Ok(Self(Suit::ALL[suit as usize], Rank::ALL[rank as usize]))
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ParseCardError {
+	Empty,
+	InvalidRank(String),
+	InvalidSuit(String),
+	InvalidGlyph(char),
+	MissingSuit(String),
+}
+
+impl fmt::Display for ParseCardError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Empty => f.write_str("Expected a card but found nothing"),
+			Self::InvalidRank(rank) => write!(
+				f,
+				"Invalid rank `{}`, expected one of A, 2-10, T, J, Q or K",
+				rank
+			),
+			Self::InvalidSuit(suit) => write!(
+				f,
+				"Invalid suit `{}`, expected one of C, D, H, S, ♣, ♦, ♥ or ♠",
+				suit
+			),
+			Self::InvalidGlyph(glyph) => {
+				write!(f, "`{}` is not a playing card", glyph)
+			}
+			Self::MissingSuit(card) => {
+				write!(f, "Card `{}` is missing a suit", card)
+			}
+		}
+	}
+}
+
+impl std::error::Error for ParseCardError {}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::deck::DECK_52;
+
+	#[test]
+	fn glyphs_round_trip() {
+		for card in DECK_52.cards() {
+			let glyph = card.to_string();
+			assert_eq!(glyph.chars().count(), 1);
+			assert_eq!(glyph.parse::<Card>(), Ok(*card));
+		}
+	}
+
+	#[test]
+	fn text_round_trips() {
+		for card in DECK_52.cards() {
+			let text = format!("{}{}", card.rank(), card.suit());
+			assert_eq!(text.parse::<Card>(), Ok(*card));
+		}
+	}
+
+	#[test]
+	fn parses_ranks_and_suits_in_any_notation() {
+		let ten_of_hearts = Card::new(Suit::Heart, Rank::Ten);
+		for text in &["10h", "10H", "Th", "tH", "10♥", "T♡", " 10h "] {
+			assert_eq!(text.parse::<Card>(), Ok(ten_of_hearts), "{}", text);
+		}
+		assert_eq!("AS".parse::<Card>(), Ok(Card::SPADE_ACE));
+		assert_eq!("k♣".parse::<Card>(), Ok(Card::CLUB_KING));
+		assert_eq!("Qd".parse::<Card>(), Ok(Card::DIAMOND_QUEEN));
+	}
+
+	#[test]
+	fn reports_what_is_wrong() {
+		assert_eq!("".parse::<Card>(), Err(ParseCardError::Empty));
+		assert_eq!("  ".parse::<Card>(), Err(ParseCardError::Empty));
+		assert_eq!(
+			"1h".parse::<Card>(),
+			Err(ParseCardError::InvalidRank("1".to_owned()))
+		);
+		assert_eq!(
+			"Ax".parse::<Card>(),
+			Err(ParseCardError::InvalidSuit("x".to_owned()))
+		);
+		assert_eq!(
+			"A".parse::<Card>(),
+			Err(ParseCardError::MissingSuit("A".to_owned()))
+		);
+		// the back of a card and the knight are no cards of the game
+		assert_eq!(
+			"\u{1F0A0}".parse::<Card>(),
+			Err(ParseCardError::InvalidGlyph('\u{1F0A0}'))
+		);
+		assert_eq!(
+			"\u{1F0AC}".parse::<Card>(),
+			Err(ParseCardError::InvalidGlyph('\u{1F0AC}'))
+		);
+		assert_eq!(
+			"é".parse::<Card>(),
+			Err(ParseCardError::InvalidGlyph('é'))
+		);
+	}
+}
@@ -0,0 +1,220 @@
+use std::fmt;
+
+use crate::analysis::ExpectedValues;
+use crate::card::Card;
+use crate::counting::{Counter, CountingSystem, Row};
+use crate::event::{Event, PlayerEvent};
+use crate::game::Decision;
+use crate::hand::{Hand, HandId};
+use crate::rules::Rules;
+
+// A decision which differs from the one of the coach. `cost` is the expected
+// value lost in bets of the hand, if both decisions can be valued.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mistake {
+	pub id: HandId,
+	pub hand: Hand,
+	pub upcard: Card,
+	pub decision: Decision,
+	pub optimal: Decision,
+	pub cost: Option<f64>,
+}
+
+impl fmt::Display for Mistake {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"{} against {}: {} instead of {}",
+			Row::new(&self.hand, true),
+			self.upcard,
+			self.decision,
+			self.optimal
+		)?;
+		if let Some(cost) = self.cost {
+			write!(f, ", costs {:.4} bets", cost)?;
+		}
+		Ok(())
+	}
+}
+
+// Follows the events of a table which has a coach and prices the mistakes
+// of the players by the exact expected values of the cards left in the shoe.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Coach {
+	rules: Rules,
+	// keeps the cards left in the shoe, the count itself is not used
+	counter: Counter,
+	decisions: u64,
+	mistakes: Vec<Mistake>,
+}
+
+impl Coach {
+	pub fn new(rules: Rules, decks: usize) -> Self {
+		Self {
+			rules,
+			counter: Counter::new(CountingSystem::HI_LO, decks),
+			decisions: 0,
+			mistakes: Vec::new(),
+		}
+	}
+
+	// Starts over with a freshly shuffled shoe.
+	pub fn shuffle(&mut self) {
+		self.counter.shuffle();
+	}
+
+	// Takes the cards revealed by `event` out of the shoe like the `Counter`
+	// and records the mistakes.
+	pub fn observe(&mut self, event: &Event<'_>) {
+		self.counter.observe(event);
+		match event {
+			Event::Player {
+				event: PlayerEvent::ExecuteDecision(_), ..
+			} => self.decisions += 1,
+			Event::Player {
+				player,
+				hand: index,
+				event: PlayerEvent::Mistake { decision, optimal, hand, upcard },
+			} => {
+				let values = ExpectedValues::new(
+					hand,
+					upcard,
+					self.counter.shoe(),
+					&self.rules,
+				);
+				let cost = values
+					.value(*optimal)
+					.zip(values.value(*decision))
+					.map(|(optimal, decision)| optimal - decision);
+
+				self.mistakes.push(Mistake {
+					id: HandId::new(*player, *index),
+					hand: hand.clone().into_owned(),
+					upcard: *upcard,
+					decision: *decision,
+					optimal: *optimal,
+					cost,
+				});
+			}
+			_ => {}
+		}
+	}
+
+	// Decisions executed by the players.
+	pub const fn decisions(&self) -> u64 {
+		self.decisions
+	}
+
+	pub fn mistakes(&self) -> &[Mistake] {
+		&self.mistakes
+	}
+
+	pub fn last_mistake(&self) -> Option<&Mistake> {
+		self.mistakes.last()
+	}
+
+	// The expected value all mistakes cost in bets.
+	pub fn cost(&self) -> f64 {
+		// an empty sum is -0
+		self.mistakes.iter().filter_map(|mistake| mistake.cost).sum::<f64>()
+			+ 0.0
+	}
+}
+
+impl fmt::Display for Coach {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"{} mistakes in {} decisions, costing {:.4} bets",
+			self.mistakes.len(),
+			self.decisions,
+			self.cost()
+		)?;
+		for mistake in &self.mistakes {
+			write!(f, "\n  Player {} {}", mistake.id, mistake)?;
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::borrow::Cow;
+
+	use super::*;
+	use crate::analysis::Composition;
+	use crate::event::DealerEvent;
+	use crate::score::BlackjackScore;
+
+	fn initial(player: usize, hand: &Hand) -> Event<'_> {
+		Event::player(
+			player,
+			0,
+			PlayerEvent::InitialHand {
+				hand: Cow::Borrowed(hand),
+				score: hand.score(&BlackjackScore),
+			},
+		)
+	}
+
+	#[test]
+	fn coach_without_mistakes_costs_nothing() {
+		let coach = Coach::new(Rules::default(), 1);
+		assert_eq!(coach.cost(), 0.0);
+		assert_eq!(
+			coach.to_string(),
+			"0 mistakes in 0 decisions, costing 0.0000 bets"
+		);
+	}
+
+	// The deck runs out in the middle of a round, the cards dealt before go
+	// back into the shoe while the ones on the table stay out.
+	#[test]
+	fn mistakes_are_priced_by_the_shoe_after_a_shuffle() {
+		let rules = Rules::default();
+		let mut coach = Coach::new(rules, 1);
+		let sixteen = "Th 6c".parse::<Hand>().unwrap();
+		let nines = "9h 9d".parse::<Hand>().unwrap();
+		let upcard = Card::SPADE_SIX;
+
+		coach.observe(&Event::dealer(DealerEvent::Draw(Card::CLUB_TWO)));
+		coach.observe(&initial(0, &sixteen));
+		coach.observe(&initial(1, &nines));
+		coach.observe(&Event::dealer(DealerEvent::Upcard(upcard)));
+		let table = sixteen.cards().iter().chain(nines.cards()).copied();
+		coach.observe(&Event::dealer(DealerEvent::Shuffle {
+			table: table.chain(Some(upcard)).collect(),
+		}));
+		coach.observe(&Event::player(
+			1,
+			0,
+			PlayerEvent::Mistake {
+				decision: Decision::Stand,
+				optimal: Decision::Split,
+				hand: Cow::Borrowed(&nines),
+				upcard,
+			},
+		));
+		coach.observe(&Event::player(
+			1,
+			0,
+			PlayerEvent::ExecuteDecision(Decision::Stand),
+		));
+
+		let mut shoe = Composition::decks(1);
+		for card in sixteen.cards().iter().chain(nines.cards()) {
+			shoe.remove(card);
+		}
+		shoe.remove(&upcard);
+		let values = ExpectedValues::new(&nines, &upcard, &shoe, &rules);
+		let cost = values.split.unwrap() - values.stand;
+		assert!(cost > 0.0);
+
+		let mistake = coach.last_mistake().unwrap();
+		assert_eq!(mistake.id, HandId::new(1, 0));
+		assert_eq!(mistake.cost, Some(cost));
+		assert_eq!(coach.cost(), cost);
+		assert_eq!(coach.decisions(), 1);
+		assert_eq!(coach.mistakes().len(), 1);
+	}
+}
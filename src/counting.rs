@@ -0,0 +1,622 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::analysis::Composition;
+use crate::card::Card;
+use crate::deck::{Deck, DECK_52};
+use crate::event::{DealerEvent, Event, PlayerEvent};
+use crate::game::Decision;
+use crate::hand::Hand;
+use crate::rules::Rules;
+use crate::score::BlackjackScore;
+use crate::strategy::{Play, Strategy};
+
+// The tags a counting system adds to the running count for the card values 1
+// (aces) to 10. The thresholds are true counts of the system: `insurance`
+// where insurance starts to pay and `favorable` where the player gains the
+// edge and should raise the bet.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct CountingSystem {
+	pub tags: [f64; 10],
+	pub insurance: f64,
+	pub favorable: f64,
+}
+
+impl CountingSystem {
+	pub const HI_LO: Self = Self::new(
+		[-1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 0.0, 0.0, 0.0, -1.0],
+		3.0,
+		1.0,
+	);
+	pub const KO: Self = Self::new(
+		[-1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 1.0, 0.0, 0.0, -1.0],
+		3.0,
+		1.0,
+	);
+	pub const HI_OPT_I: Self = Self::new(
+		[0.0, 0.0, 1.0, 1.0, 1.0, 1.0, 0.0, 0.0, 0.0, -1.0],
+		2.0,
+		1.0,
+	);
+	pub const HI_OPT_II: Self = Self::new(
+		[0.0, 1.0, 1.0, 2.0, 2.0, 1.0, 1.0, 0.0, 0.0, -2.0],
+		6.0,
+		2.0,
+	);
+	pub const OMEGA_II: Self = Self::new(
+		[0.0, 1.0, 1.0, 2.0, 2.0, 2.0, 1.0, 0.0, -1.0, -2.0],
+		6.0,
+		2.0,
+	);
+	pub const ZEN: Self = Self::new(
+		[-1.0, 1.0, 1.0, 2.0, 2.0, 2.0, 1.0, 0.0, 0.0, -2.0],
+		6.0,
+		2.0,
+	);
+	pub const WONG_HALVES: Self = Self::new(
+		[-1.0, 0.5, 1.0, 1.0, 1.5, 1.0, 0.5, 0.0, -0.5, -1.0],
+		3.0,
+		1.0,
+	);
+
+	pub const fn new(tags: [f64; 10], insurance: f64, favorable: f64) -> Self {
+		Self { tags, insurance, favorable }
+	}
+
+	pub const fn tag(&self, card: &Card) -> f64 {
+		self.tags[BlackjackScore::value(card.rank()) as usize - 1]
+	}
+
+	// The running count of a whole deck, zero for balanced systems.
+	pub fn imbalance(&self) -> f64 {
+		DECK_52.cards().map(|card| self.tag(card)).sum()
+	}
+
+	pub fn is_balanced(&self) -> bool {
+		self.imbalance() == 0.0
+	}
+}
+
+// A built-in system by name like `hi-lo` or `omega-ii`, or ten custom tags
+// for aces to tens like `-1,1,1,1,1,1,0,0,0,-1`.
+impl FromStr for CountingSystem {
+	type Err = ParseCountingSystemError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let name = s.trim().to_ascii_lowercase().replace(['_', ' '], "-");
+		match name.as_str() {
+			"hi-lo" | "hilo" => return Ok(Self::HI_LO),
+			"ko" => return Ok(Self::KO),
+			"hi-opt-i" | "hi-opt-1" => return Ok(Self::HI_OPT_I),
+			"hi-opt-ii" | "hi-opt-2" => return Ok(Self::HI_OPT_II),
+			"omega-ii" | "omega-2" => return Ok(Self::OMEGA_II),
+			"zen" => return Ok(Self::ZEN),
+			"wong-halves" | "halves" => return Ok(Self::WONG_HALVES),
+			_ => {}
+		}
+
+		let tags = s
+			.split(',')
+			.map(|tag| tag.trim().parse::<f64>())
+			.collect::<Result<Vec<_>, _>>()
+			.ok()
+			.filter(|tags| tags.len() == 10)
+			.ok_or_else(|| ParseCountingSystemError(s.trim().to_string()))?;
+
+		let mut system = Self::HI_LO;
+		system.tags.copy_from_slice(&tags);
+		Ok(system)
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ParseCountingSystemError(pub String);
+
+impl fmt::Display for ParseCountingSystemError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"Invalid counting system `{}`, expected hi-lo, ko, hi-opt-i, \
+			 hi-opt-ii, omega-ii, zen, wong-halves or ten tags for aces to tens",
+			self.0
+		)
+	}
+}
+
+impl std::error::Error for ParseCountingSystemError {}
+
+// Keeps the count of a shoe with `decks` decks from the cards revealed at
+// the table, and the cards not seen yet. Unbalanced systems start with an
+// initial running count which puts their pivot at the imbalance of a deck.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct Counter {
+	system: CountingSystem,
+	decks: usize,
+	running: f64,
+	seen: usize,
+	shoe: Composition,
+}
+
+impl Counter {
+	pub fn new(system: CountingSystem, decks: usize) -> Self {
+		let mut counter = Self {
+			system,
+			decks,
+			running: 0.0,
+			seen: 0,
+			shoe: Composition::default(),
+		};
+		counter.shuffle();
+		counter
+	}
+
+	pub const fn system(&self) -> &CountingSystem {
+		&self.system
+	}
+
+	pub const fn decks(&self) -> usize {
+		self.decks
+	}
+
+	// Starts over with a freshly shuffled shoe.
+	pub fn shuffle(&mut self) {
+		self.running = self.initial_count();
+		self.seen = 0;
+		self.shoe = Composition::decks(self.decks);
+	}
+
+	fn initial_count(&self) -> f64 {
+		0.0 - self.system.imbalance() * (self.decks as f64 - 1.0)
+	}
+
+	pub fn count(&mut self, card: &Card) {
+		self.running += self.system.tag(card);
+		self.seen += 1;
+		self.shoe.remove(card);
+	}
+
+	// Counts the cards revealed by `event`. Split hands are not counted
+	// again, their cards were revealed by the hand they were split from.
+	pub fn observe(&mut self, event: &Event<'_>) {
+		match event {
+			Event::Player {
+				hand: 0,
+				event: PlayerEvent::InitialHand { hand, .. },
+				..
+			} => {
+				for card in hand.cards() {
+					self.count(card);
+				}
+			}
+			Event::Player { event: PlayerEvent::Draw(card), .. }
+			| Event::Dealer(DealerEvent::Draw(card))
+			| Event::Dealer(DealerEvent::Upcard(card))
+			| Event::Dealer(DealerEvent::HoleCard(card)) => self.count(card),
+			// the new shoe is missing the cards on the table
+			Event::Dealer(DealerEvent::Shuffle { table }) => {
+				self.shuffle();
+				for card in table {
+					self.count(card);
+				}
+			}
+			_ => {}
+		}
+	}
+
+	pub const fn running_count(&self) -> f64 {
+		self.running
+	}
+
+	pub const fn cards_seen(&self) -> usize {
+		self.seen
+	}
+
+	// The cards left in the shoe.
+	pub const fn shoe(&self) -> &Composition {
+		&self.shoe
+	}
+
+	pub fn decks_remaining(&self) -> f64 {
+		let cards = (self.decks * DECK_52.len()).saturating_sub(self.seen);
+		cards as f64 / DECK_52.len() as f64
+	}
+
+	// The running count per remaining deck. For unbalanced systems the
+	// imbalance of the cards seen so far is taken out first.
+	pub fn true_count(&self) -> f64 {
+		let decks_seen = self.seen as f64 / DECK_52.len() as f64;
+		let balanced = self.running
+			- self.initial_count()
+			- self.system.imbalance() * decks_seen;
+
+		// the last cards of a shoe are counted as half a deck
+		balanced / self.decks_remaining().max(0.5)
+	}
+
+	pub fn insurance(&self) -> bool {
+		self.true_count() >= self.system.insurance
+	}
+
+	pub fn favorable(&self) -> bool {
+		self.true_count() >= self.system.favorable
+	}
+}
+
+// Bets by the true count: each step is a true count and the units bet from
+// that count on. Below the lowest step the lowest step is bet, zero units
+// sit the round out.
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct BetRamp {
+	steps: Vec<(f64, u64)>,
+}
+
+impl BetRamp {
+	pub fn new(mut steps: Vec<(f64, u64)>) -> Self {
+		steps.sort_by(|a, b| a.0.total_cmp(&b.0));
+		Self { steps }
+	}
+
+	pub fn flat(units: u64) -> Self {
+		Self::new(vec![(0.0, units)])
+	}
+
+	pub fn steps(&self) -> &[(f64, u64)] {
+		&self.steps
+	}
+
+	pub fn units(&self, true_count: f64) -> u64 {
+		self.steps
+			.iter()
+			.take_while(|(count, _)| true_count >= *count)
+			.last()
+			.or_else(|| self.steps.first())
+			.map_or(1, |(_, units)| *units)
+	}
+}
+
+// Steps like `1:2,2:4,3:8` of a true count and the units bet from it on.
+impl FromStr for BetRamp {
+	type Err = ParseBetRampError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let steps = s
+			.split(',')
+			.map(|step| {
+				let (count, units) = step.split_once(':')?;
+				Some((count.trim().parse().ok()?, units.trim().parse().ok()?))
+			})
+			.collect::<Option<Vec<_>>>()
+			.filter(|steps| !steps.is_empty())
+			.ok_or_else(|| ParseBetRampError(s.trim().to_string()))?;
+
+		Ok(Self::new(steps))
+	}
+}
+
+impl fmt::Display for BetRamp {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		for (i, (count, units)) in self.steps.iter().enumerate() {
+			if i > 0 {
+				f.write_str(",")?;
+			}
+			write!(f, "{}:{}", count, units)?;
+		}
+		Ok(())
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ParseBetRampError(pub String);
+
+impl fmt::Display for ParseBetRampError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"Invalid bet ramp `{}`, expected true counts and units like \
+			 1:2,2:4,3:8",
+			self.0
+		)
+	}
+}
+
+impl std::error::Error for ParseBetRampError {}
+
+// The row of a strategy chart an index play deviates in, with pairs by the
+// value of their cards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Row {
+	Hard(u8),
+	Soft(u8),
+	Pair(u8),
+}
+
+impl Row {
+	// The row `hand` is played by, pairs only while they can be split.
+	pub fn new(hand: &Hand, can_split: bool) -> Self {
+		if let (true, [card, _]) = (can_split && hand.is_pair(), hand.cards())
+		{
+			return Self::Pair(BlackjackScore::value(card.rank()));
+		}
+
+		match hand.score(&BlackjackScore) {
+			(_, Some(soft)) => Self::Soft(soft),
+			(hard, None) => Self::Hard(hard),
+		}
+	}
+}
+
+impl fmt::Display for Row {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Hard(total) => write!(f, "hard {}", total),
+			Self::Soft(total) => write!(f, "soft {}", total),
+			Self::Pair(1) => f.write_str("pair of aces"),
+			Self::Pair(value) => write!(f, "pair of {}s", value),
+		}
+	}
+}
+
+// Plays `play` against the upcard `up` (1 for aces) when the true count is
+// at or above `index`, or below it if `above` is false. Otherwise the basic
+// strategy stays.
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub struct IndexPlay {
+	pub row: Row,
+	pub up: u8,
+	pub index: f64,
+	pub above: bool,
+	pub play: Play,
+}
+
+impl IndexPlay {
+	// The Hi-Lo indices of the Illustrious 18 for a shoe game. Insurance, the
+	// first of them, is the insurance threshold of the counting system.
+	// Standing on 15 and 16 surrenders first where it is allowed.
+	pub const ILLUSTRIOUS_18: [Self; 17] = [
+		Self::at(Row::Hard(16), 10, 0.0, Play::SurrenderOrStand),
+		Self::at(Row::Hard(15), 10, 4.0, Play::SurrenderOrStand),
+		Self::at(Row::Pair(10), 5, 5.0, Play::Split),
+		Self::at(Row::Pair(10), 6, 4.0, Play::Split),
+		Self::at(Row::Hard(10), 10, 4.0, Play::DoubleOrHit),
+		Self::at(Row::Hard(12), 3, 2.0, Play::Stand),
+		Self::at(Row::Hard(12), 2, 3.0, Play::Stand),
+		Self::at(Row::Hard(11), 1, 1.0, Play::DoubleOrHit),
+		Self::at(Row::Hard(9), 2, 1.0, Play::DoubleOrHit),
+		Self::at(Row::Hard(10), 1, 4.0, Play::DoubleOrHit),
+		Self::at(Row::Hard(9), 7, 3.0, Play::DoubleOrHit),
+		Self::at(Row::Hard(16), 9, 5.0, Play::SurrenderOrStand),
+		Self::below(Row::Hard(13), 2, -1.0, Play::Hit),
+		Self::below(Row::Hard(12), 4, 0.0, Play::Hit),
+		Self::below(Row::Hard(12), 5, -2.0, Play::Hit),
+		Self::below(Row::Hard(12), 6, -1.0, Play::Hit),
+		Self::below(Row::Hard(13), 3, -2.0, Play::Hit),
+	];
+
+	// The Hi-Lo surrender indices of the Fab 4.
+	pub const FAB_4: [Self; 4] = [
+		Self::at(Row::Hard(14), 10, 3.0, Play::SurrenderOrHit),
+		Self::at(Row::Hard(15), 10, 0.0, Play::SurrenderOrHit),
+		Self::at(Row::Hard(15), 9, 2.0, Play::SurrenderOrHit),
+		Self::at(Row::Hard(15), 1, 1.0, Play::SurrenderOrHit),
+	];
+
+	pub const fn at(row: Row, up: u8, index: f64, play: Play) -> Self {
+		Self { row, up, index, above: true, play }
+	}
+
+	pub const fn below(row: Row, up: u8, index: f64, play: Play) -> Self {
+		Self { row, up, index, above: false, play }
+	}
+
+	// Whether the play applies to `hand`, which is only played by its pair
+	// row while it can be split.
+	fn matches(
+		&self,
+		hand: &Hand,
+		upcard: &Card,
+		true_count: f64,
+		legal: &dyn Fn(Decision) -> bool,
+	) -> bool {
+		if BlackjackScore::value(upcard.rank()) != self.up
+			|| (true_count >= self.index) != self.above
+		{
+			return false;
+		}
+
+		self.row == Row::new(hand, legal(Decision::Split))
+	}
+
+	// Surrendering instead of hitting deviates from nothing else, unlike
+	// surrendering instead of standing or splitting.
+	const fn only_surrenders(&self) -> bool {
+		matches!(self.play, Play::SurrenderOrHit)
+	}
+}
+
+// An automated counter who sizes bets by the true count, insures when the
+// count says so and deviates from a strategy by index plays, the first
+// matching one winning.
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub struct CountingPlayer {
+	pub system: CountingSystem,
+	pub ramp: BetRamp,
+	pub index_plays: Vec<IndexPlay>,
+}
+
+impl CountingPlayer {
+	pub const fn new(system: CountingSystem, ramp: BetRamp) -> Self {
+		Self { system, ramp, index_plays: Vec::new() }
+	}
+
+	pub fn decide(
+		&self,
+		strategy: &dyn Strategy,
+		hand: &Hand,
+		upcard: &Card,
+		rules: &Rules,
+		true_count: f64,
+		legal: &dyn Fn(Decision) -> bool,
+	) -> Decision {
+		// surrenders which are not allowed leave the next index play, or
+		// fall back to standing or splitting
+		self.index_plays
+			.iter()
+			.find(|play| {
+				play.matches(hand, upcard, true_count, legal)
+					&& (!play.only_surrenders() || legal(Decision::Surrender))
+			})
+			.map_or_else(
+				|| strategy.decide(hand, upcard, rules, legal),
+				|play| play.play.decision(legal),
+			)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::iter::FromIterator;
+
+	use super::*;
+	use crate::rules::SurrenderRule;
+	use crate::strategy::BasicStrategy;
+
+	fn player() -> CountingPlayer {
+		let mut player =
+			CountingPlayer::new(CountingSystem::HI_LO, BetRamp::flat(1));
+		player.index_plays.extend_from_slice(&IndexPlay::FAB_4);
+		player.index_plays.extend_from_slice(&IndexPlay::ILLUSTRIOUS_18);
+		player
+	}
+
+	fn decide(
+		cards: &[Card],
+		upcard: Card,
+		rules: &Rules,
+		tc: f64,
+	) -> Decision {
+		let hand = Hand::from_iter(cards.iter().copied());
+		let surrender =
+			rules.surrender != SurrenderRule::None && hand.cards().len() == 2;
+		player().decide(
+			&BasicStrategy::new(6),
+			&hand,
+			&upcard,
+			rules,
+			tc,
+			&|decision| match decision {
+				Decision::Hit | Decision::Stand => true,
+				Decision::Surrender => surrender,
+				_ => false,
+			},
+		)
+	}
+
+	const SIXTEEN: [Card; 2] = [Card::CLUB_TEN, Card::HEART_SIX];
+
+	#[test]
+	fn stands_on_16_against_10_without_surrender() {
+		let rules = Rules { surrender: SurrenderRule::None, ..Rules::new() };
+		let ten = Card::SPADE_KING;
+		assert_eq!(decide(&SIXTEEN, ten, &rules, -0.5), Decision::Hit);
+		assert_eq!(decide(&SIXTEEN, ten, &rules, 0.0), Decision::Stand);
+		assert_eq!(decide(&SIXTEEN, ten, &rules, 2.0), Decision::Stand);
+	}
+
+	#[test]
+	fn surrenders_16_against_10_where_allowed() {
+		let ten = Card::SPADE_KING;
+		assert_eq!(
+			decide(&SIXTEEN, ten, &Rules::new(), 0.0),
+			Decision::Surrender
+		);
+	}
+
+	#[test]
+	fn stands_on_a_16_of_three_cards() {
+		let three = [Card::CLUB_SEVEN, Card::HEART_FIVE, Card::DIAMOND_FOUR];
+		let ten = Card::SPADE_TEN;
+		assert_eq!(decide(&three, ten, &Rules::new(), -1.0), Decision::Hit);
+		assert_eq!(decide(&three, ten, &Rules::new(), 0.0), Decision::Stand);
+	}
+
+	#[test]
+	fn surrenders_by_the_fab_4_only_where_allowed() {
+		let fifteen = [Card::CLUB_TEN, Card::HEART_FIVE];
+		let ten = Card::SPADE_QUEEN;
+		let rules = Rules { surrender: SurrenderRule::None, ..Rules::new() };
+		assert_eq!(
+			decide(&fifteen, ten, &Rules::new(), 1.0),
+			Decision::Surrender
+		);
+		assert_eq!(decide(&fifteen, ten, &rules, 1.0), Decision::Hit);
+		// the Illustrious 18 stand on 15 although the Fab 4 come first
+		assert_eq!(decide(&fifteen, ten, &rules, 4.0), Decision::Stand);
+	}
+
+	#[test]
+	fn counter_keeps_the_cards_left_in_the_shoe() {
+		let mut counter = Counter::new(CountingSystem::HI_LO, 2);
+		counter.observe(&Event::Dealer(DealerEvent::Upcard(Card::CLUB_ACE)));
+		counter.observe(&Event::Dealer(DealerEvent::Shuffle {
+			table: vec![Card::HEART_TEN, Card::SPADE_FIVE],
+		}));
+		assert_eq!(counter.cards_seen(), 2);
+		assert_eq!(counter.running_count(), 0.0);
+		assert_eq!(counter.shoe().len(), 102);
+		assert_eq!(counter.shoe().count(1), 8);
+		assert_eq!(counter.shoe().count(10), 31);
+		assert_eq!(counter.shoe().count(5), 7);
+
+		counter.shuffle();
+		assert_eq!(*counter.shoe(), Composition::decks(2));
+	}
+
+	#[test]
+	fn true_count_divides_by_the_decks_left() {
+		let mut counter = Counter::new(CountingSystem::HI_LO, 6);
+		assert_eq!(counter.true_count(), 0.0);
+		for _ in 0..20 {
+			counter.count(&Card::CLUB_FIVE);
+			counter.count(&Card::HEART_SIX);
+		}
+		for _ in 0..12 {
+			counter.count(&Card::SPADE_KING);
+		}
+		assert_eq!(counter.running_count(), 28.0);
+		assert_eq!(counter.decks_remaining(), 5.0);
+		assert!((counter.true_count() - 5.6).abs() < 1e-9);
+		assert!(counter.insurance());
+		assert!(counter.favorable());
+	}
+
+	#[test]
+	fn true_count_of_the_last_cards_counts_half_a_deck() {
+		let mut counter = Counter::new(CountingSystem::HI_LO, 1);
+		for card in DECK_52.cards().take(50) {
+			counter.count(card);
+		}
+		// the queen and the king of spades are left
+		assert_eq!(counter.running_count(), 2.0);
+		assert_eq!(counter.true_count(), 4.0);
+	}
+
+	// KO is unbalanced by four per deck and starts below zero in shoes.
+	#[test]
+	fn ko_starts_with_an_initial_running_count() {
+		assert_eq!(CountingSystem::KO.imbalance(), 4.0);
+		assert!(!CountingSystem::KO.is_balanced());
+		assert_eq!(Counter::new(CountingSystem::KO, 1).running_count(), 0.0);
+
+		let mut counter = Counter::new(CountingSystem::KO, 6);
+		assert_eq!(counter.running_count(), -20.0);
+		assert_eq!(counter.true_count(), 0.0);
+		for card in DECK_52.cards() {
+			counter.count(card);
+		}
+		assert_eq!(counter.running_count(), -16.0);
+		assert_eq!(counter.true_count(), 0.0);
+
+		counter.shuffle();
+		assert_eq!(counter.running_count(), -20.0);
+	}
+}
@@ -0,0 +1,451 @@
+use rand::prelude::SliceRandom as _;
+use rand::{thread_rng, RngCore};
+
+use crate::card::Card;
+
+pub trait Deck {
+	fn shuffle(&mut self) {
+		self.shuffle_with(&mut thread_rng())
+	}
+
+	// Shuffles with the given random number generator. The same seed always
+	// produces the same order of cards.
+	fn shuffle_with(&mut self, rng: &mut dyn RngCore);
+
+	fn draw(&mut self) -> Option<Card>;
+
+	fn len(&self) -> usize;
+
+	fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	// Whether the deck has to be shuffled before the next game.
+	fn needs_shuffle(&self) -> bool {
+		true
+	}
+
+	// Shuffles the dealt cards back in when the deck runs out in the middle
+	// of a game. The cards still on the `table` stay out of the deck. Decks
+	// which can not take back their cards return `false`.
+	fn reshuffle(&mut self, _table: &[Card], _rng: &mut dyn RngCore) -> bool {
+		false
+	}
+}
+
+impl<D> Deck for &mut D
+where
+	D: Deck + ?Sized,
+{
+	fn shuffle(&mut self) {
+		(**self).shuffle()
+	}
+
+	fn shuffle_with(&mut self, rng: &mut dyn RngCore) {
+		(**self).shuffle_with(rng)
+	}
+
+	fn draw(&mut self) -> Option<Card> {
+		(**self).draw()
+	}
+
+	fn len(&self) -> usize {
+		(**self).len()
+	}
+
+	fn is_empty(&self) -> bool {
+		(**self).is_empty()
+	}
+
+	fn needs_shuffle(&self) -> bool {
+		(**self).needs_shuffle()
+	}
+
+	fn reshuffle(&mut self, table: &[Card], rng: &mut dyn RngCore) -> bool {
+		(**self).reshuffle(table, rng)
+	}
+}
+
+pub type Deck52 = ArrayDeck<52>;
+#[rustfmt::skip]
+pub const DECK_52: Deck52 = Deck52::new([
+    Card::CLUB_ACE, Card::CLUB_TWO, Card::CLUB_THREE, Card::CLUB_FOUR,
+    Card::CLUB_FIVE, Card::CLUB_SIX, Card::CLUB_SEVEN, Card::CLUB_EIGHT,
+    Card::CLUB_NINE, Card::CLUB_TEN, Card::CLUB_JACK, Card::CLUB_QUEEN,
+    Card::CLUB_KING,
+    Card::DIAMOND_ACE, Card::DIAMOND_TWO, Card::DIAMOND_THREE,
+    Card::DIAMOND_FOUR, Card::DIAMOND_FIVE, Card::DIAMOND_SIX,
+    Card::DIAMOND_SEVEN, Card::DIAMOND_EIGHT, Card::DIAMOND_NINE,
+    Card::DIAMOND_TEN, Card::DIAMOND_JACK, Card::DIAMOND_QUEEN,
+    Card::DIAMOND_KING,
+    Card::HEART_ACE, Card::HEART_TWO, Card::HEART_THREE, Card::HEART_FOUR,
+    Card::HEART_FIVE, Card::HEART_SIX, Card::HEART_SEVEN, Card::HEART_EIGHT,
+    Card::HEART_NINE, Card::HEART_TEN, Card::HEART_JACK, Card::HEART_QUEEN,
+    Card::HEART_KING,
+    Card::SPADE_ACE, Card::SPADE_TWO, Card::SPADE_THREE, Card::SPADE_FOUR,
+    Card::SPADE_FIVE, Card::SPADE_SIX, Card::SPADE_SEVEN, Card::SPADE_EIGHT,
+    Card::SPADE_NINE, Card::SPADE_TEN, Card::SPADE_JACK, Card::SPADE_QUEEN,
+    Card::SPADE_KING,
+]);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ArrayDeck<const SIZE: usize> {
+	// index on which the last valid card resides. All cards 0..valid_idx should
+	// be valid.
+	valid_idx: usize,
+	cards: [Card; SIZE],
+}
+
+impl<const SIZE: usize> ArrayDeck<SIZE> {
+	pub const fn new(cards: [Card; SIZE]) -> Self {
+		Self { valid_idx: SIZE, cards }
+	}
+
+	pub fn cards(&self) -> Iter<'_> {
+		Iter::new(&self.cards[..self.valid_idx])
+	}
+}
+
+impl<const SIZE: usize> Deck for ArrayDeck<SIZE> {
+	fn shuffle_with(&mut self, rng: &mut dyn RngCore) {
+		self.cards[..self.valid_idx].shuffle(rng)
+	}
+
+	fn draw(&mut self) -> Option<Card> {
+		if self.is_empty() {
+			None
+		} else {
+			self.valid_idx -= 1;
+			Some(self.cards[self.valid_idx])
+		}
+	}
+
+	fn len(&self) -> usize {
+		self.valid_idx
+	}
+
+	// A full deck may still be in order, a partly dealt one is played until
+	// it runs out.
+	fn needs_shuffle(&self) -> bool {
+		self.valid_idx == SIZE || self.is_empty()
+	}
+
+	// Drawn cards stay behind `valid_idx`, the ones on the table are kept
+	// there.
+	fn reshuffle(&mut self, table: &[Card], rng: &mut dyn RngCore) -> bool {
+		self.valid_idx = SIZE - set_aside(&mut self.cards, table);
+		self.shuffle_with(rng);
+		true
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct VecDeck {
+	// like in `ArrayDeck` all cards 0..valid_idx are still in the deck
+	valid_idx: usize,
+	cards: Vec<Card>,
+}
+
+impl VecDeck {
+	pub const fn new(cards: Vec<Card>) -> Self {
+		Self { valid_idx: cards.len(), cards }
+	}
+}
+
+impl Deck for VecDeck {
+	fn shuffle_with(&mut self, rng: &mut dyn RngCore) {
+		self.cards[..self.valid_idx].shuffle(rng);
+	}
+
+	fn draw(&mut self) -> Option<Card> {
+		if self.is_empty() {
+			None
+		} else {
+			self.valid_idx -= 1;
+			Some(self.cards[self.valid_idx])
+		}
+	}
+
+	fn len(&self) -> usize {
+		self.valid_idx
+	}
+
+	fn needs_shuffle(&self) -> bool {
+		self.valid_idx == self.cards.len() || self.is_empty()
+	}
+
+	fn reshuffle(&mut self, table: &[Card], rng: &mut dyn RngCore) -> bool {
+		self.valid_idx = self.cards.len() - set_aside(&mut self.cards, table);
+		self.shuffle_with(rng);
+		true
+	}
+}
+
+// Multiple decks shuffled together. Cards are not removed when drawn, so a
+// shuffle always restores the complete shoe. A new shoe and a shoe where the
+// cut card was reached ask to be shuffled before the next game.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Shoe {
+	cards: Vec<Card>,
+	// index of the next card to draw
+	next: usize,
+	// index at which the cut card is placed
+	cut_card: usize,
+}
+
+impl Shoe {
+	// `penetration` is the fraction of the shoe which is dealt before the
+	// cut card comes out.
+	pub fn new(decks: usize, penetration: f64) -> Self {
+		let cards = (0..decks)
+			.flat_map(|_| DECK_52.cards().copied().collect::<Vec<_>>())
+			.collect::<Vec<_>>();
+
+		Self::with_cards(cards, penetration)
+	}
+
+	pub fn with_cards(cards: Vec<Card>, penetration: f64) -> Self {
+		let penetration = penetration.clamp(0.0, 1.0);
+		let cut_card = (cards.len() as f64 * penetration).round() as usize;
+
+		Self { next: cards.len(), cards, cut_card }
+	}
+
+	pub fn decks(&self) -> usize {
+		self.cards.len() / DECK_52.len()
+	}
+
+	pub const fn dealt(&self) -> usize {
+		self.next
+	}
+
+	pub const fn cut_card_reached(&self) -> bool {
+		self.next >= self.cut_card
+	}
+
+	pub fn cards(&self) -> Iter<'_> {
+		Iter::new(&self.cards[self.next..])
+	}
+}
+
+impl Deck for Shoe {
+	fn shuffle_with(&mut self, rng: &mut dyn RngCore) {
+		self.cards.shuffle(rng);
+		self.next = 0;
+	}
+
+	fn draw(&mut self) -> Option<Card> {
+		let card = self.cards.get(self.next).copied();
+		if card.is_some() {
+			self.next += 1;
+		}
+		card
+	}
+
+	fn len(&self) -> usize {
+		self.cards.len() - self.next
+	}
+
+	fn needs_shuffle(&self) -> bool {
+		self.cut_card_reached()
+	}
+
+	// The cards on the table count as dealt, the cut card stays in place.
+	fn reshuffle(&mut self, table: &[Card], rng: &mut dyn RngCore) -> bool {
+		let kept = set_aside(&mut self.cards, table);
+		let len = self.cards.len() - kept;
+		self.cards[..len].shuffle(rng);
+		self.cards.rotate_right(kept);
+		self.next = kept;
+		true
+	}
+}
+
+// Moves a card equal to each card of `table` to the end of `cards` and
+// returns how many of them were found.
+fn set_aside(cards: &mut [Card], table: &[Card]) -> usize {
+	let mut len = cards.len();
+	for card in table {
+		if let Some(idx) = cards[..len].iter().position(|other| other == card)
+		{
+			len -= 1;
+			cards.swap(idx, len);
+		}
+	}
+	cards.len() - len
+}
+
+// Deals the cards in the given order and never shuffles.
+#[cfg(test)]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(crate) struct Stacked(Vec<Card>);
+
+#[cfg(test)]
+impl Stacked {
+	// The cards in the order they are drawn, like "Th 7c 9s".
+	pub(crate) fn new(cards: &str) -> Self {
+		Self(
+			cards
+				.split_whitespace()
+				.rev()
+				.map(|card| card.parse().unwrap())
+				.collect(),
+		)
+	}
+}
+
+#[cfg(test)]
+impl Deck for Stacked {
+	fn shuffle_with(&mut self, _rng: &mut dyn RngCore) {}
+
+	fn draw(&mut self) -> Option<Card> {
+		self.0.pop()
+	}
+
+	fn len(&self) -> usize {
+		self.0.len()
+	}
+
+	fn needs_shuffle(&self) -> bool {
+		false
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Iter<'a> {
+	idx: usize,
+	cards: &'a [Card],
+}
+
+impl<'a> Iter<'a> {
+	pub const fn new(cards: &'a [Card]) -> Self {
+		Self { idx: 0, cards }
+	}
+}
+
+impl<'a> Iterator for Iter<'a> {
+	type Item = &'a Card;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.idx == self.cards.len() {
+			None
+		} else {
+			let card = &self.cards[self.idx];
+			self.idx += 1;
+			Some(card)
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use rand::rngs::StdRng;
+	use rand::SeedableRng;
+
+	use super::*;
+
+	fn sorted(cards: impl IntoIterator<Item = Card>) -> Vec<Card> {
+		let mut cards = cards.into_iter().collect::<Vec<_>>();
+		cards.sort();
+		cards
+	}
+
+	// Draws everything left and checks it is the full deck without `table`.
+	fn assert_without(deck: &mut dyn Deck, decks: usize, table: &[Card]) {
+		let mut left = sorted(
+			(0..decks)
+				.flat_map(|_| DECK_52.cards().copied())
+				.collect::<Vec<_>>(),
+		);
+		for card in table {
+			let idx = left.iter().position(|other| other == card).unwrap();
+			left.remove(idx);
+		}
+		assert_eq!(deck.len(), left.len());
+		assert_eq!(sorted(std::iter::from_fn(|| deck.draw())), left);
+	}
+
+	#[test]
+	fn same_seed_shuffles_the_same_order() {
+		let order = |seed| {
+			let mut shoe = Shoe::new(2, 0.75);
+			shoe.shuffle_with(&mut StdRng::seed_from_u64(seed));
+			let mut deck = DECK_52;
+			deck.shuffle_with(&mut StdRng::seed_from_u64(seed));
+			(shoe.cards().copied().collect::<Vec<_>>(), deck)
+		};
+		assert_eq!(order(1), order(1));
+		assert_ne!(order(1).0, order(2).0);
+		assert_ne!(order(1).1, order(2).1);
+	}
+
+	#[test]
+	fn shoe_asks_for_a_shuffle_at_the_cut_card() {
+		let mut shoe = Shoe::new(6, 0.75);
+		assert_eq!(shoe.decks(), 6);
+		assert!(shoe.needs_shuffle());
+
+		shoe.shuffle_with(&mut StdRng::seed_from_u64(1));
+		assert_eq!(shoe.len(), 312);
+		assert!(!shoe.needs_shuffle());
+		for _ in 0..233 {
+			shoe.draw().unwrap();
+		}
+		assert!(!shoe.cut_card_reached());
+		shoe.draw().unwrap();
+		assert_eq!(shoe.dealt(), 234);
+		assert!(shoe.cut_card_reached());
+		assert!(shoe.needs_shuffle());
+		assert_eq!(shoe.cards().count(), 78);
+	}
+
+	#[test]
+	fn penetration_is_a_fraction_of_the_shoe() {
+		let mut shoe = Shoe::new(1, 2.0);
+		shoe.shuffle_with(&mut StdRng::seed_from_u64(1));
+		for _ in 0..51 {
+			shoe.draw().unwrap();
+		}
+		assert!(!shoe.needs_shuffle());
+		shoe.draw().unwrap();
+		assert!(shoe.needs_shuffle());
+		assert_eq!(shoe.draw(), None);
+
+		let mut shoe = Shoe::new(2, 0.0);
+		shoe.shuffle_with(&mut StdRng::seed_from_u64(1));
+		assert!(shoe.needs_shuffle());
+	}
+
+	#[test]
+	fn reshuffled_shoe_keeps_the_table_cards_out() {
+		let mut rng = StdRng::seed_from_u64(2);
+		let mut shoe = Shoe::new(2, 0.5);
+		shoe.shuffle_with(&mut rng);
+		let table = (0..60).map(|_| shoe.draw().unwrap()).collect::<Vec<_>>();
+		let table = &table[55..];
+
+		assert!(shoe.reshuffle(table, &mut rng));
+		// the table cards count as dealt towards the cut card
+		assert_eq!(shoe.dealt(), 5);
+		assert!(!shoe.needs_shuffle());
+		assert_without(&mut shoe, 2, table);
+	}
+
+	#[test]
+	fn decks_take_back_all_but_the_table_cards() {
+		let mut rng = StdRng::seed_from_u64(3);
+		let mut deck = DECK_52;
+		deck.shuffle_with(&mut rng);
+		let table = (0..20).map(|_| deck.draw().unwrap()).collect::<Vec<_>>();
+		assert!(deck.reshuffle(&table[..4], &mut rng));
+		assert_eq!(deck.cards().count(), 48);
+		assert_without(&mut deck, 1, &table[..4]);
+
+		let mut deck = VecDeck::new(DECK_52.cards().copied().collect());
+		deck.shuffle_with(&mut rng);
+		let table = (0..52).map(|_| deck.draw().unwrap()).collect::<Vec<_>>();
+		assert!(deck.is_empty());
+		assert!(deck.reshuffle(&table[50..], &mut rng));
+		assert_without(&mut deck, 1, &table[50..]);
+	}
+}
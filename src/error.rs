@@ -0,0 +1,41 @@
+use std::borrow::Cow;
+use std::fmt;
+
+use crate::game::Action;
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum BlackjackError {
+	// The deck ran out of cards in the middle of a round, the round can not
+	// be finished.
+	DeckExhausted,
+	IllegalAction { action: Action, reason: Cow<'static, str> },
+	InvalidSeat(usize),
+	SeatTaken(usize),
+	EmptySeat(usize),
+	InsufficientBankroll { amount: u64, bankroll: u64 },
+}
+
+impl BlackjackError {
+	pub(crate) const fn illegal(action: Action, reason: &'static str) -> Self {
+		Self::IllegalAction { action, reason: Cow::Borrowed(reason) }
+	}
+}
+
+impl fmt::Display for BlackjackError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::DeckExhausted => f.write_str("The deck is out of cards"),
+			Self::IllegalAction { reason, .. } => f.write_str(reason),
+			Self::InvalidSeat(seat) => write!(f, "There is no seat {}", seat),
+			Self::SeatTaken(seat) => {
+				write!(f, "Seat {} is already taken", seat)
+			}
+			Self::EmptySeat(seat) => write!(f, "Seat {} is empty", seat),
+			Self::InsufficientBankroll { amount, bankroll } => {
+				write!(f, "{} exceeds the bankroll of {}", amount, bankroll)
+			}
+		}
+	}
+}
+
+impl std::error::Error for BlackjackError {}
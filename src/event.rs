@@ -0,0 +1,277 @@
+use std::borrow::Cow;
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::card::Card;
+use crate::game::Decision;
+use crate::hand::{Hand, HandId};
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Event<'a> {
+	Player { player: usize, hand: usize, event: PlayerEvent<'a> },
+	Dealer(DealerEvent<'a>),
+	Win(WinEvent),
+}
+
+impl<'a> Event<'a> {
+	pub const fn player(
+		player: usize,
+		hand: usize,
+		event: PlayerEvent<'a>,
+	) -> Self {
+		Self::Player { player, hand, event }
+	}
+
+	pub const fn dealer(event: DealerEvent<'a>) -> Self {
+		Self::Dealer(event)
+	}
+
+	pub const fn win(event: WinEvent) -> Self {
+		Self::Win(event)
+	}
+
+	// The event with hands of its own instead of borrowed ones, which can
+	// be kept after the round.
+	pub fn into_owned(self) -> Event<'static> {
+		match self {
+			Self::Player { player, hand, event } => {
+				Event::Player { player, hand, event: event.into_owned() }
+			}
+			Self::Dealer(event) => Event::Dealer(event.into_owned()),
+			Self::Win(event) => Event::Win(event),
+		}
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum PlayerEvent<'a> {
+	TurnStart,
+	TurnEnd,
+	ChooseDecision(Decision),
+	// The coach would have decided `optimal` for `hand` against `upcard`.
+	Mistake {
+		decision: Decision,
+		optimal: Decision,
+		hand: Cow<'a, Hand>,
+		upcard: Card,
+	},
+	ExecuteDecision(Decision),
+	InvalidDecision {
+		invalid_decision: Decision,
+		reason: Cow<'static, str>,
+	},
+	Bet(u64),
+	InvalidBet {
+		amount: u64,
+		reason: Cow<'static, str>,
+	},
+	Draw(Card),
+	InitialHand {
+		hand: Cow<'a, Hand>,
+		score: (u8, Option<u8>),
+	},
+	HandChange {
+		hand: Cow<'a, Hand>,
+		score: (u8, Option<u8>),
+	},
+	// The acting hand was split, `hand` is the index of the new sub-hand.
+	Split {
+		hand: usize,
+	},
+	Bust {
+		score: u8,
+	},
+	Surrender,
+	Blackjack,
+	Insurance(u64),
+	EvenMoney,
+	InsurancePayout {
+		wager: u64,
+		payout: u64,
+		bankroll: u64,
+	},
+	// `payout` is the amount returned to the bankroll, including the wager.
+	Payout {
+		outcome: Outcome,
+		wager: u64,
+		payout: u64,
+		bankroll: u64,
+	},
+}
+
+impl PlayerEvent<'_> {
+	pub fn into_owned(self) -> PlayerEvent<'static> {
+		match self {
+			Self::TurnStart => PlayerEvent::TurnStart,
+			Self::TurnEnd => PlayerEvent::TurnEnd,
+			Self::ChooseDecision(decision) => {
+				PlayerEvent::ChooseDecision(decision)
+			}
+			Self::Mistake { decision, optimal, hand, upcard } => {
+				PlayerEvent::Mistake {
+					decision,
+					optimal,
+					hand: Cow::Owned(hand.into_owned()),
+					upcard,
+				}
+			}
+			Self::ExecuteDecision(decision) => {
+				PlayerEvent::ExecuteDecision(decision)
+			}
+			Self::InvalidDecision { invalid_decision, reason } => {
+				PlayerEvent::InvalidDecision { invalid_decision, reason }
+			}
+			Self::Bet(amount) => PlayerEvent::Bet(amount),
+			Self::InvalidBet { amount, reason } => {
+				PlayerEvent::InvalidBet { amount, reason }
+			}
+			Self::Draw(card) => PlayerEvent::Draw(card),
+			Self::InitialHand { hand, score } => PlayerEvent::InitialHand {
+				hand: Cow::Owned(hand.into_owned()),
+				score,
+			},
+			Self::HandChange { hand, score } => PlayerEvent::HandChange {
+				hand: Cow::Owned(hand.into_owned()),
+				score,
+			},
+			Self::Split { hand } => PlayerEvent::Split { hand },
+			Self::Bust { score } => PlayerEvent::Bust { score },
+			Self::Surrender => PlayerEvent::Surrender,
+			Self::Blackjack => PlayerEvent::Blackjack,
+			Self::Insurance(amount) => PlayerEvent::Insurance(amount),
+			Self::EvenMoney => PlayerEvent::EvenMoney,
+			Self::InsurancePayout { wager, payout, bankroll } => {
+				PlayerEvent::InsurancePayout { wager, payout, bankroll }
+			}
+			Self::Payout { outcome, wager, payout, bankroll } => {
+				PlayerEvent::Payout { outcome, wager, payout, bankroll }
+			}
+		}
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum DealerEvent<'a> {
+	TurnStart,
+	TurnEnd,
+	Decision(Decision),
+	Draw(Card),
+	Upcard(Card),
+	HoleCard(Card),
+	HandChange { hand: Cow<'a, Hand>, score: (u8, Option<u8>) },
+	Bust { score: u8 },
+	Blackjack,
+	OfferInsurance,
+	// The dealer checked the hole card for a blackjack.
+	Peek,
+	// The deck ran out and the discards were shuffled back in. `table` are
+	// the cards on the table the players have seen, they and the hole card
+	// stay out of the deck.
+	Shuffle { table: Vec<Card> },
+}
+
+impl DealerEvent<'_> {
+	pub fn into_owned(self) -> DealerEvent<'static> {
+		match self {
+			Self::TurnStart => DealerEvent::TurnStart,
+			Self::TurnEnd => DealerEvent::TurnEnd,
+			Self::Decision(decision) => DealerEvent::Decision(decision),
+			Self::Draw(card) => DealerEvent::Draw(card),
+			Self::Upcard(card) => DealerEvent::Upcard(card),
+			Self::HoleCard(card) => DealerEvent::HoleCard(card),
+			Self::HandChange { hand, score } => DealerEvent::HandChange {
+				hand: Cow::Owned(hand.into_owned()),
+				score,
+			},
+			Self::Bust { score } => DealerEvent::Bust { score },
+			Self::Blackjack => DealerEvent::Blackjack,
+			Self::OfferInsurance => DealerEvent::OfferInsurance,
+			Self::Peek => DealerEvent::Peek,
+			Self::Shuffle { table } => DealerEvent::Shuffle { table },
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Outcome {
+	Blackjack,
+	EvenMoney,
+	Win,
+	Push,
+	Lose,
+	Surrender,
+}
+
+impl fmt::Display for Outcome {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Blackjack => f.write_str("blackjack"),
+			Self::EvenMoney => f.write_str("even money"),
+			Self::Win => f.write_str("win"),
+			Self::Push => f.write_str("push"),
+			Self::Lose => f.write_str("lose"),
+			Self::Surrender => f.write_str("surrender"),
+		}
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum WinEvent {
+	Dealer,
+	Players(Vec<HandId>),
+	Push(Vec<HandId>),
+	Lose(Vec<HandId>),
+}
+
+#[cfg(test)]
+mod tests {
+	use std::iter::FromIterator;
+
+	use super::*;
+
+	#[test]
+	fn owned_events_outlive_the_hand() {
+		let (player, dealer) = {
+			let hand =
+				Hand::from_iter(vec![Card::HEART_TEN, Card::CLUB_SEVEN]);
+			let player = Event::player(
+				0,
+				1,
+				PlayerEvent::HandChange {
+					hand: Cow::Borrowed(&hand),
+					score: (17, None),
+				},
+			);
+			let dealer = Event::dealer(DealerEvent::HandChange {
+				hand: Cow::Borrowed(&hand),
+				score: (17, None),
+			});
+			(player.into_owned(), dealer.into_owned())
+		};
+
+		let hand = Hand::from_iter(vec![Card::HEART_TEN, Card::CLUB_SEVEN]);
+		match player {
+			Event::Player {
+				player: 0,
+				hand: 1,
+				event:
+					PlayerEvent::HandChange { hand: Cow::Owned(owned), score },
+			} => assert_eq!((owned, score), (hand.clone(), (17, None))),
+			event => panic!("{:?}", event),
+		}
+		match dealer {
+			Event::Dealer(DealerEvent::HandChange {
+				hand: Cow::Owned(owned),
+				..
+			}) => assert_eq!(owned, hand),
+			event => panic!("{:?}", event),
+		}
+	}
+}
@@ -0,0 +1,1929 @@
+use std::borrow::Cow;
+use std::fmt;
+
+use rand::rngs::StdRng;
+use rand::{Rng, RngCore, SeedableRng};
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::card::{Card, Rank};
+use crate::deck::Deck;
+use crate::error::BlackjackError;
+use crate::event::{DealerEvent, Event, Outcome, PlayerEvent, WinEvent};
+use crate::hand::{Hand, HandId};
+use crate::rules::{DoubleDownRule, HoleCardRule, Rules, SurrenderRule};
+use crate::score::{BlackjackScore, Score};
+use crate::strategy::{Chart, Strategy};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Decision {
+	Hit,
+	Stand,
+	DoubleDown,
+	Split,
+	Surrender,
+}
+
+impl fmt::Display for Decision {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		// TODO: real
+		fmt::Debug::fmt(&self, f)
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Wager {
+	Bet,
+	// Insurance against a dealer blackjack of up to `max`. Taking the full
+	// insurance on a blackjack is even money, which needs no stake.
+	Insurance { max: u64 },
+}
+
+// The step a round is in. Phases which need no input from the players are
+// advanced with `Action::Continue`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Phase {
+	Betting { seat: usize },
+	Dealing,
+	Insurance { seat: usize },
+	// Early surrender is offered before the dealer checks for a blackjack.
+	// Any other decision is kept until it is the player's turn.
+	EarlySurrender { seat: usize },
+	PlayerTurn { seat: usize, hand: usize },
+	DealerTurn,
+	// The round is over and all hands are paid.
+	Settlement,
+}
+
+impl Phase {
+	// The hand which has to act in this phase.
+	pub const fn hand(&self) -> Option<HandId> {
+		match *self {
+			Self::Betting { seat }
+			| Self::Insurance { seat }
+			| Self::EarlySurrender { seat } => Some(HandId::new(seat, 0)),
+			Self::PlayerTurn { seat, hand } => Some(HandId::new(seat, hand)),
+			Self::Dealing | Self::DealerTurn | Self::Settlement => None,
+		}
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Action {
+	Bet(u64),
+	Insurance(u64),
+	Decision(Decision),
+	Continue,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Blackjack<D, S> {
+	dealer: Hand,
+	hole: Option<Card>,
+	seats: Vec<Seat>,
+	deck: D,
+	score: S,
+	rules: Rules,
+	phase: Phase,
+	// hands which are out of the game with their final outcome
+	outcomes: Vec<(HandId, Outcome)>,
+	blackjacks: Vec<HandId>,
+	// decisions players made while being offered early surrender, which are
+	// executed once it is their turn
+	pending: Vec<Option<Decision>>,
+	// chart the decisions of the players are compared with
+	coach: Option<Chart>,
+	// seed for shuffling the discards back in, drawn from the generator the
+	// game was created with to replay the same cards
+	seed: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+struct Seat {
+	bankroll: u64,
+	// The initial bet of the round.
+	bet: u64,
+	// Every player starts out with a single hand which can grow into multiple
+	// hands by splitting. Each hand carries its own wager.
+	hands: Vec<Hand>,
+	wagers: Vec<u64>,
+	insurance: u64,
+}
+
+impl Seat {
+	fn new(hand: Hand, bankroll: u64) -> Self {
+		Self {
+			bankroll,
+			bet: 0,
+			hands: vec![hand],
+			wagers: vec![0],
+			insurance: 0,
+		}
+	}
+}
+
+impl<D> Blackjack<D, BlackjackScore>
+where
+	D: Deck,
+{
+	pub fn with_players(
+		players: usize,
+		bankroll: u64,
+		deck: D,
+		rules: Rules,
+		rng: &mut dyn RngCore,
+	) -> Self {
+		Self::with_hands(
+			Hand::default(),
+			vec![Hand::default(); players],
+			bankroll,
+			deck,
+			rules,
+			rng,
+		)
+	}
+
+	pub fn with_bankrolls(
+		bankrolls: &[u64],
+		deck: D,
+		rules: Rules,
+		rng: &mut dyn RngCore,
+	) -> Self {
+		let seats = bankrolls
+			.iter()
+			.map(|&bankroll| Seat::new(Hand::default(), bankroll))
+			.collect();
+
+		Self::with_seats(Hand::default(), seats, deck, rules, rng)
+	}
+
+	pub fn with_hands(
+		dealer: Hand,
+		hands: Vec<Hand>,
+		bankroll: u64,
+		deck: D,
+		rules: Rules,
+		rng: &mut dyn RngCore,
+	) -> Self {
+		let seats =
+			hands.into_iter().map(|hand| Seat::new(hand, bankroll)).collect();
+
+		Self::with_seats(dealer, seats, deck, rules, rng)
+	}
+
+	fn with_seats(
+		dealer: Hand,
+		seats: Vec<Seat>,
+		mut deck: D,
+		rules: Rules,
+		rng: &mut dyn RngCore,
+	) -> Self {
+		if deck.needs_shuffle() {
+			deck.shuffle_with(rng);
+		}
+		let seed = rng.next_u64();
+
+		let mut game = Self {
+			dealer,
+			hole: None,
+			pending: vec![None; seats.len()],
+			seats,
+			deck,
+			score: BlackjackScore,
+			rules,
+			phase: Phase::Dealing,
+			outcomes: Vec::new(),
+			blackjacks: Vec::new(),
+			coach: None,
+			seed,
+		};
+		game.next_bet(0);
+		game
+	}
+
+	pub const fn rules(&self) -> &Rules {
+		&self.rules
+	}
+
+	pub const fn coach(&self) -> Option<&Chart> {
+		self.coach.as_ref()
+	}
+
+	// Decisions which differ from the chart of the coach are reported with
+	// a `PlayerEvent::Mistake`.
+	pub const fn set_coach(&mut self, coach: Option<Chart>) {
+		self.coach = coach;
+	}
+
+	// Only the face up cards of the dealer, without the hole card.
+	pub const fn dealer(&self) -> &Hand {
+		&self.dealer
+	}
+
+	pub fn upcard(&self) -> Option<&Card> {
+		self.dealer.cards().first()
+	}
+
+	pub fn player(&self, player: usize) -> Option<&[Hand]> {
+		self.seats.get(player).map(|seat| seat.hands.as_slice())
+	}
+
+	pub fn hand(&self, player: usize, hand: usize) -> Option<&Hand> {
+		self.seats.get(player).and_then(|seat| seat.hands.get(hand))
+	}
+
+	pub fn score(
+		&self,
+		player: usize,
+		hand: usize,
+	) -> Option<<BlackjackScore as Score>::Output> {
+		self.hand(player, hand).map(|hand| hand.score(&self.score))
+	}
+
+	pub fn bankroll(&self, player: usize) -> Option<u64> {
+		self.seats.get(player).map(|seat| seat.bankroll)
+	}
+
+	pub fn wager(&self, player: usize, hand: usize) -> Option<u64> {
+		self.seats.get(player).and_then(|seat| seat.wagers.get(hand)).copied()
+	}
+
+	pub const fn players(&self) -> usize {
+		self.seats.len()
+	}
+
+	// Whether the hand may make `decision` right now.
+	pub fn is_legal(
+		&self,
+		player: usize,
+		hand: usize,
+		decision: Decision,
+	) -> bool {
+		self.hand(player, hand).is_some()
+			&& self.check_decision(player, hand, decision).is_ok()
+	}
+
+	// The decision `strategy` recommends for the hand.
+	pub fn advise(
+		&self,
+		strategy: &dyn Strategy,
+		player: usize,
+		hand: usize,
+	) -> Option<Decision> {
+		let upcard = self.upcard()?;
+		let current = self.hand(player, hand)?;
+
+		Some(strategy.decide(current, upcard, &self.rules, &|decision| {
+			self.is_legal(player, hand, decision)
+		}))
+	}
+
+	// Every card on the table including a hole card which was never revealed.
+	pub(crate) fn cards(&self) -> impl Iterator<Item = &Card> + '_ {
+		self.dealer.cards().iter().chain(self.hole.iter()).chain(
+			self.seats.iter().flat_map(|seat| {
+				seat.hands.iter().flat_map(|hand| hand.cards().iter())
+			}),
+		)
+	}
+
+	pub const fn phase(&self) -> Phase {
+		self.phase
+	}
+
+	// All actions which can be applied in the current phase. For wagers the
+	// maximum amount is listed, any amount up to it is legal.
+	pub fn legal_actions(&self) -> Vec<Action> {
+		match self.phase {
+			Phase::Betting { seat } => {
+				vec![Action::Bet(self.seats[seat].bankroll)]
+			}
+			Phase::Insurance { seat } => {
+				vec![Action::Insurance(self.max_insurance(seat))]
+			}
+			Phase::EarlySurrender { seat } => self.legal_decisions(seat, 0),
+			Phase::PlayerTurn { seat, hand } => {
+				self.legal_decisions(seat, hand)
+			}
+			Phase::Dealing | Phase::DealerTurn => vec![Action::Continue],
+			Phase::Settlement => Vec::new(),
+		}
+	}
+
+	fn legal_decisions(&self, player: usize, hand: usize) -> Vec<Action> {
+		[
+			Decision::Hit,
+			Decision::Stand,
+			Decision::DoubleDown,
+			Decision::Split,
+			Decision::Surrender,
+		]
+		.iter()
+		.filter(|&&decision| {
+			self.check_decision(player, hand, decision).is_ok()
+		})
+		.map(|&decision| Action::Decision(decision))
+		.collect()
+	}
+
+	// Advances the round by a single action and returns everything which
+	// happened because of it. An illegal action leaves the round untouched.
+	pub fn apply(
+		&mut self,
+		action: Action,
+	) -> Result<Vec<Event<'static>>, BlackjackError> {
+		let mut events = Vec::new();
+
+		match (self.phase, action) {
+			(Phase::Betting { seat: player }, Action::Bet(amount)) => {
+				let bankroll = self.seats[player].bankroll;
+				if amount > bankroll {
+					return Err(BlackjackError::InsufficientBankroll {
+						amount,
+						bankroll,
+					});
+				}
+
+				let seat = &mut self.seats[player];
+				if amount == 0 {
+					// the player sits out this round
+					seat.hands.clear();
+					seat.wagers.clear();
+				} else {
+					seat.bankroll -= amount;
+					seat.bet = amount;
+					seat.wagers[0] = amount;
+					events.push(Event::player(
+						player,
+						0,
+						PlayerEvent::Bet(amount),
+					));
+				}
+
+				self.next_bet(player + 1);
+			}
+			(Phase::Dealing, Action::Continue) => self.deal(&mut events)?,
+			(Phase::Insurance { seat: player }, Action::Insurance(amount)) => {
+				let max = self.max_insurance(player);
+				let id = HandId::new(player, 0);
+				let even_money = amount > 0
+					&& amount == max
+					&& self.blackjacks.contains(&id);
+				let bankroll = self.seats[player].bankroll;
+				if amount > max && amount <= bankroll {
+					return Err(BlackjackError::illegal(
+						action,
+						"Insurance exceeds half the bet",
+					));
+				} else if !even_money && amount > bankroll {
+					return Err(BlackjackError::InsufficientBankroll {
+						amount,
+						bankroll,
+					});
+				}
+
+				if amount == 0 {
+					// the player declined
+				} else if even_money {
+					// even money is paid right away and needs no insurance
+					events.push(Event::player(
+						player,
+						0,
+						PlayerEvent::EvenMoney,
+					));
+					self.outcomes.push((id, Outcome::EvenMoney));
+				} else {
+					let seat = &mut self.seats[player];
+					seat.bankroll -= amount;
+					seat.insurance = amount;
+					events.push(Event::player(
+						player,
+						0,
+						PlayerEvent::Insurance(amount),
+					));
+				}
+
+				self.next_insurance(player + 1, &mut events)?;
+			}
+			(
+				Phase::EarlySurrender { seat: player },
+				Action::Decision(decision),
+			) => {
+				self.check_decision(player, 0, decision).map_err(
+					|reason| BlackjackError::illegal(action, reason),
+				)?;
+				self.check_mistake(player, 0, decision, &mut events);
+
+				if decision == Decision::Surrender {
+					events.push(Event::player(
+						player,
+						0,
+						PlayerEvent::ChooseDecision(decision),
+					));
+					events.push(Event::player(
+						player,
+						0,
+						PlayerEvent::ExecuteDecision(decision),
+					));
+					events.push(Event::player(
+						player,
+						0,
+						PlayerEvent::Surrender,
+					));
+					self.outcomes
+						.push((HandId::new(player, 0), Outcome::Surrender));
+				} else {
+					// executed once it is the player's turn
+					self.pending[player] = Some(decision);
+				}
+
+				self.next_surrender(player + 1, &mut events)?;
+			}
+			(
+				Phase::PlayerTurn { seat: player, hand },
+				Action::Decision(decision),
+			) => {
+				self.check_decision(player, hand, decision).map_err(
+					|reason| BlackjackError::illegal(action, reason),
+				)?;
+				self.check_mistake(player, hand, decision, &mut events);
+
+				if !self.decide(player, hand, decision, &mut events)?
+					|| self.is_turn_over(player, hand)
+				{
+					self.end_turn(player, hand, &mut events);
+					self.next_turn(player, hand + 1, &mut events)?;
+				}
+			}
+			(Phase::DealerTurn, Action::Continue) => {
+				self.play_dealer(&mut events)?
+			}
+			_ => {
+				return Err(BlackjackError::illegal(
+					action,
+					"Action is not allowed in this phase",
+				))
+			}
+		}
+
+		Ok(events)
+	}
+
+	// Plays a single round and hands back the finished game. Illegal actions
+	// are reported as events and asked for again.
+	pub fn run<W, I, E>(
+		mut self,
+		mut wager: W,
+		mut input: I,
+		mut event: E,
+	) -> Result<Self, BlackjackError>
+	where
+		W: FnMut(usize, Wager, &Self) -> u64,
+		I: FnMut(usize, usize, &Self) -> Decision,
+		E: for<'a> FnMut(Event<'a>),
+	{
+		loop {
+			let action = match self.phase {
+				Phase::Betting { seat } => {
+					Action::Bet(wager(seat, Wager::Bet, &self))
+				}
+				Phase::Insurance { seat } => {
+					let max = self.max_insurance(seat);
+					Action::Insurance(wager(
+						seat,
+						Wager::Insurance { max },
+						&self,
+					))
+				}
+				Phase::EarlySurrender { seat } => {
+					Action::Decision(input(seat, 0, &self))
+				}
+				Phase::PlayerTurn { seat, hand } => {
+					Action::Decision(input(seat, hand, &self))
+				}
+				Phase::Dealing | Phase::DealerTurn => Action::Continue,
+				Phase::Settlement => break,
+			};
+
+			let id = self.phase.hand();
+			match (self.apply(action), id) {
+				(Ok(events), _) => events.into_iter().for_each(&mut event),
+				(Err(BlackjackError::DeckExhausted), _) => {
+					return Err(BlackjackError::DeckExhausted)
+				}
+				(Err(error), Some(id)) => {
+					let invalid =
+						|invalid| Event::player(id.player, id.hand, invalid);
+					let reason = match error {
+						BlackjackError::IllegalAction { reason, .. } => reason,
+						error => Cow::Owned(error.to_string()),
+					};
+					match action {
+						Action::Bet(amount) | Action::Insurance(amount) => {
+							event(invalid(PlayerEvent::InvalidBet {
+								amount,
+								reason,
+							}))
+						}
+						Action::Decision(decision) => {
+							event(invalid(PlayerEvent::ChooseDecision(
+								decision,
+							)));
+							event(invalid(PlayerEvent::InvalidDecision {
+								invalid_decision: decision,
+								reason,
+							}));
+						}
+						Action::Continue => {}
+					}
+				}
+				(Err(error), None) => return Err(error),
+			}
+		}
+
+		Ok(self)
+	}
+
+	// Moves on to the next seat which can place a bet, empty seats and broke
+	// players sit out.
+	fn next_bet(&mut self, from: usize) {
+		for seat in from..self.seats.len() {
+			if self.seats[seat].bankroll > 0 {
+				self.phase = Phase::Betting { seat };
+				return;
+			}
+
+			self.seats[seat].hands.clear();
+			self.seats[seat].wagers.clear();
+		}
+
+		self.phase = Phase::Dealing;
+	}
+
+	fn deal(
+		&mut self,
+		events: &mut Vec<Event<'static>>,
+	) -> Result<(), BlackjackError> {
+		// the whole deal comes from one deck, so the cards of a hand are seen
+		// before the deck can be shuffled again
+		let hands = self.seats.iter().filter(|seat| !seat.hands.is_empty());
+		let hole = usize::from(self.rules.hole_card == HoleCardRule::Peek);
+		if self.deck.len() < 2 * hands.count() + 1 + hole {
+			self.shuffle_discards(events)?;
+		}
+
+		// TODO: maybe first 1 for each and then give second round
+		for player in 0..self.seats.len() {
+			if self.seats[player].hands.is_empty() {
+				continue;
+			}
+
+			for _ in 0..2 {
+				let card = self.draw(events)?;
+				self.seats[player].hands[0].add(card);
+			}
+			let hand = &self.seats[player].hands[0];
+			events.push(Event::player(
+				player,
+				0,
+				PlayerEvent::InitialHand {
+					hand: Cow::Owned(hand.clone()),
+					score: hand.score(&self.score),
+				},
+			));
+		}
+
+		let upcard = self.draw(events)?;
+		self.dealer.add(upcard);
+		events.push(Event::dealer(DealerEvent::Upcard(upcard)));
+
+		if self.rules.hole_card == HoleCardRule::Peek {
+			self.hole = Some(self.draw(events)?);
+		}
+
+		self.blackjacks = self
+			.hand_ids()
+			.filter(|id| {
+				self.seats[id.player].hands[id.hand].score(&self.score).1
+					== Some(21)
+			})
+			.collect();
+
+		for id in &self.blackjacks {
+			events.push(Event::player(
+				id.player,
+				id.hand,
+				PlayerEvent::Blackjack,
+			));
+		}
+
+		if self.upcard_value() == Some(1) {
+			events.push(Event::dealer(DealerEvent::OfferInsurance));
+			self.next_insurance(0, events)
+		} else {
+			self.next_surrender(0, events)
+		}
+	}
+
+	fn next_insurance(
+		&mut self,
+		from: usize,
+		events: &mut Vec<Event<'static>>,
+	) -> Result<(), BlackjackError> {
+		match (from..self.seats.len())
+			.find(|&seat| !self.seats[seat].hands.is_empty())
+		{
+			Some(seat) => {
+				self.phase = Phase::Insurance { seat };
+				Ok(())
+			}
+			None => self.next_surrender(0, events),
+		}
+	}
+
+	// Early surrender is offered before the dealer checks for a blackjack.
+	fn next_surrender(
+		&mut self,
+		from: usize,
+		events: &mut Vec<Event<'static>>,
+	) -> Result<(), BlackjackError> {
+		let upcard = self.upcard_value();
+		if self.rules.surrender == SurrenderRule::Early
+			&& (upcard == Some(1) || upcard == Some(10))
+		{
+			let next = (from..self.seats.len()).find(|&seat| {
+				let id = HandId::new(seat, 0);
+				!self.seats[seat].hands.is_empty()
+					&& !self.blackjacks.contains(&id)
+					&& !self.is_settled(id)
+			});
+
+			if let Some(seat) = next {
+				self.phase = Phase::EarlySurrender { seat };
+				return Ok(());
+			}
+		}
+
+		self.peek(events)
+	}
+
+	fn peek(
+		&mut self,
+		events: &mut Vec<Event<'static>>,
+	) -> Result<(), BlackjackError> {
+		if self.rules.hole_card == HoleCardRule::Peek {
+			let upcard = self.upcard_value();
+			if upcard == Some(1) || upcard == Some(10) {
+				events.push(Event::dealer(DealerEvent::Peek));
+
+				let dealer_blackjack = self.has_dealer_blackjack();
+				self.settle_insurance(dealer_blackjack, events);
+
+				if dealer_blackjack {
+					self.reveal_dealer(events)?;
+					events.push(Event::dealer(DealerEvent::Blackjack));
+
+					for id in self.remaining() {
+						if self.blackjacks.contains(&id) {
+							self.outcomes.push((id, Outcome::Push));
+						} else {
+							self.outcomes.push((id, Outcome::Lose));
+						}
+					}
+
+					self.settle(false, events);
+					return Ok(());
+				}
+			}
+
+			// after the peek a blackjack wins right away
+			let blackjacks = self
+				.blackjacks
+				.iter()
+				.filter(|id| !self.is_settled(**id))
+				.map(|id| (*id, Outcome::Blackjack))
+				.collect::<Vec<_>>();
+			self.outcomes.extend(blackjacks);
+		}
+
+		self.next_turn(0, 0, events)
+	}
+
+	// Starts the turn of the next hand that has to be played. Hands which
+	// need no input, like split aces, are finished right away.
+	fn next_turn(
+		&mut self,
+		mut player: usize,
+		mut hand: usize,
+		events: &mut Vec<Event<'static>>,
+	) -> Result<(), BlackjackError> {
+		while player < self.seats.len() {
+			// split hands are appended to the player's hands, so the length
+			// might grow while playing
+			if hand >= self.seats[player].hands.len() {
+				player += 1;
+				hand = 0;
+				continue;
+			}
+
+			let id = HandId::new(player, hand);
+			if self.is_settled(id) || self.blackjacks.contains(&id) {
+				hand += 1;
+				continue;
+			}
+
+			events.push(Event::player(player, hand, PlayerEvent::TurnStart));
+			self.phase = Phase::PlayerTurn { seat: player, hand };
+
+			// hands created by a split only hold a single card until their
+			// turn
+			if self.seats[player].hands[hand].cards().len() == 1 {
+				self.draw_player(player, hand, events)?;
+			}
+
+			let mut over = self.is_turn_over(player, hand);
+			if let Some(decision) =
+				self.pending[player].take().filter(|_| !over)
+			{
+				match self.check_decision(player, hand, decision) {
+					Ok(()) => {
+						over = !self.decide(player, hand, decision, events)?
+							|| self.is_turn_over(player, hand);
+					}
+					Err(reason) => {
+						events.push(Event::player(
+							player,
+							hand,
+							PlayerEvent::ChooseDecision(decision),
+						));
+						events.push(Event::player(
+							player,
+							hand,
+							PlayerEvent::InvalidDecision {
+								invalid_decision: decision,
+								reason: Cow::Borrowed(reason),
+							},
+						));
+					}
+				}
+			}
+
+			if !over {
+				return Ok(());
+			}
+
+			self.end_turn(player, hand, events);
+			hand += 1;
+		}
+
+		// the dealer only plays if there are hands left or insurance to settle
+		let insured = self.seats.iter().any(|seat| seat.insurance > 0);
+		if self.remaining().is_empty() && !insured {
+			self.settle(false, events);
+		} else {
+			self.phase = Phase::DealerTurn;
+		}
+
+		Ok(())
+	}
+
+	fn check_mistake(
+		&self,
+		player: usize,
+		hand: usize,
+		decision: Decision,
+		events: &mut Vec<Event<'static>>,
+	) {
+		if let (Some(coach), Some(upcard)) = (&self.coach, self.upcard()) {
+			let current = &self.seats[player].hands[hand];
+			let optimal =
+				coach.decide(current, upcard, &self.rules, &|decision| {
+					self.is_legal(player, hand, decision)
+				});
+
+			if optimal != decision {
+				events.push(Event::player(
+					player,
+					hand,
+					PlayerEvent::Mistake {
+						decision,
+						optimal,
+						hand: Cow::Owned(current.clone()),
+						upcard: *upcard,
+					},
+				));
+			}
+		}
+	}
+
+	// Executes a legal decision. Returns `false` if the hand can not act any
+	// more.
+	fn decide(
+		&mut self,
+		player: usize,
+		hand: usize,
+		decision: Decision,
+		events: &mut Vec<Event<'static>>,
+	) -> Result<bool, BlackjackError> {
+		events.push(Event::player(
+			player,
+			hand,
+			PlayerEvent::ChooseDecision(decision),
+		));
+		events.push(Event::player(
+			player,
+			hand,
+			PlayerEvent::ExecuteDecision(decision),
+		));
+
+		match decision {
+			Decision::Hit => {
+				self.draw_player(player, hand, events)?;
+				Ok(true)
+			}
+			Decision::Stand => Ok(false),
+			Decision::DoubleDown => {
+				let seat = &mut self.seats[player];
+				let wager = seat.wagers[hand];
+				seat.bankroll -= wager;
+				seat.wagers[hand] += wager;
+				events.push(Event::player(
+					player,
+					hand,
+					PlayerEvent::Bet(wager),
+				));
+
+				self.draw_player(player, hand, events)?;
+				Ok(false)
+			}
+			Decision::Split => {
+				let seat = &mut self.seats[player];
+				if let Some(split) = seat.hands[hand].split() {
+					let wager = seat.wagers[hand];
+					seat.bankroll -= wager;
+
+					let new_hand = seat.hands.len();
+					seat.hands.push(split);
+					seat.wagers.push(wager);
+					events.push(Event::player(
+						player,
+						hand,
+						PlayerEvent::Split { hand: new_hand },
+					));
+					events.push(Event::player(
+						player,
+						new_hand,
+						PlayerEvent::Bet(wager),
+					));
+
+					let split = &self.seats[player].hands[new_hand];
+					events.push(Event::player(
+						player,
+						new_hand,
+						PlayerEvent::InitialHand {
+							hand: Cow::Owned(split.clone()),
+							score: split.score(&self.score),
+						},
+					));
+
+					let current = &self.seats[player].hands[hand];
+					events.push(Event::player(
+						player,
+						hand,
+						PlayerEvent::HandChange {
+							hand: Cow::Owned(current.clone()),
+							score: current.score(&self.score),
+						},
+					));
+
+					self.draw_player(player, hand, events)?;
+				}
+				Ok(true)
+			}
+			Decision::Surrender => {
+				events.push(Event::player(
+					player,
+					hand,
+					PlayerEvent::Surrender,
+				));
+				self.outcomes
+					.push((HandId::new(player, hand), Outcome::Surrender));
+				Ok(false)
+			}
+		}
+	}
+
+	fn is_turn_over(&self, player: usize, hand: usize) -> bool {
+		// split aces receive a single card unless they can be re-split
+		self.seats[player].hands[hand].is_bust(&self.score)
+			|| (self.is_split_aces(player, hand)
+				&& !self.rules.hit_split_aces
+				&& self.check_decision(player, hand, Decision::Split).is_err())
+	}
+
+	fn end_turn(
+		&mut self,
+		player: usize,
+		hand: usize,
+		events: &mut Vec<Event<'static>>,
+	) {
+		let current = &self.seats[player].hands[hand];
+		if current.is_bust(&self.score) {
+			let score = current.score(&self.score);
+			events.push(Event::player(
+				player,
+				hand,
+				PlayerEvent::Bust { score: score.0 },
+			));
+			self.outcomes.push((HandId::new(player, hand), Outcome::Lose));
+		}
+
+		events.push(Event::player(player, hand, PlayerEvent::TurnEnd));
+	}
+
+	fn play_dealer(
+		&mut self,
+		events: &mut Vec<Event<'static>>,
+	) -> Result<(), BlackjackError> {
+		events.push(Event::dealer(DealerEvent::TurnStart));
+
+		self.reveal_dealer(events)?;
+
+		if self.rules.hole_card != HoleCardRule::Peek {
+			let dealer_blackjack = self.has_dealer_blackjack();
+			self.settle_insurance(dealer_blackjack, events);
+
+			if dealer_blackjack {
+				events.push(Event::dealer(DealerEvent::Blackjack));
+				events.push(Event::dealer(DealerEvent::TurnEnd));
+
+				for id in self.remaining() {
+					if self.blackjacks.contains(&id) {
+						self.outcomes.push((id, Outcome::Push));
+					} else {
+						self.outcomes.push((id, Outcome::Lose));
+					}
+				}
+
+				let original_bets_only = self.rules.hole_card
+					== HoleCardRule::NoHoleCard { original_bets_only: true };
+				self.settle(original_bets_only, events);
+				return Ok(());
+			}
+
+			// without a hole card a blackjack can only be paid now
+			let blackjacks = self
+				.remaining()
+				.into_iter()
+				.filter(|id| self.blackjacks.contains(id))
+				.map(|id| (id, Outcome::Blackjack))
+				.collect::<Vec<_>>();
+			self.outcomes.extend(blackjacks);
+		}
+
+		let remaining = self.remaining();
+
+		// the dealer only needs to draw if there are hands to beat
+		while !remaining.is_empty() && {
+			let score = self.dealer.score(&self.score);
+			let total = score.1.unwrap_or(score.0);
+			total < 17
+				|| (self.rules.dealer_hits_soft_17 && score.1 == Some(17))
+		} {
+			events.push(Event::dealer(DealerEvent::Decision(Decision::Hit)));
+			let card = self.draw(events)?;
+			events.push(Event::dealer(DealerEvent::Draw(card)));
+			let hand = &mut self.dealer;
+			hand.add(card);
+			events.push(Event::dealer(DealerEvent::HandChange {
+				hand: Cow::Owned(hand.clone()),
+				score: hand.score(&self.score),
+			}));
+		}
+
+		let dealer_bust = self.dealer.is_bust(&self.score);
+		if dealer_bust {
+			events.push(Event::dealer(DealerEvent::Bust {
+				score: self.dealer.score(&self.score).0,
+			}));
+		} else {
+			events.push(Event::dealer(DealerEvent::Decision(Decision::Stand)));
+		}
+
+		events.push(Event::dealer(DealerEvent::TurnEnd));
+
+		// win
+		let dealer_score = self.dealer.score(&self.score);
+		let dealer_score = dealer_score.1.unwrap_or(dealer_score.0);
+
+		for id in remaining {
+			let score =
+				self.seats[id.player].hands[id.hand].score(&self.score);
+			let score = score.1.unwrap_or(score.0);
+
+			let outcome = if dealer_bust || score > dealer_score {
+				Outcome::Win
+			} else if score == dealer_score {
+				Outcome::Push
+			} else {
+				Outcome::Lose
+			};
+			self.outcomes.push((id, outcome));
+		}
+
+		self.settle(false, events);
+		Ok(())
+	}
+
+	fn upcard_value(&self) -> Option<u8> {
+		self.upcard().map(|card| BlackjackScore::value(card.rank()))
+	}
+
+	// Reveals the hole card or, without a hole card, draws the second card.
+	fn reveal_dealer(
+		&mut self,
+		events: &mut Vec<Event<'static>>,
+	) -> Result<(), BlackjackError> {
+		let card = match self.hole.take() {
+			Some(hole) => {
+				events.push(Event::dealer(DealerEvent::HoleCard(hole)));
+				hole
+			}
+			None => {
+				let card = self.draw(events)?;
+				events.push(Event::dealer(DealerEvent::Draw(card)));
+				card
+			}
+		};
+
+		let hand = &mut self.dealer;
+		hand.add(card);
+		events.push(Event::dealer(DealerEvent::HandChange {
+			hand: Cow::Owned(hand.clone()),
+			score: hand.score(&self.score),
+		}));
+		Ok(())
+	}
+
+	// Insurance is up to half the bet and the bankroll. Even money on a
+	// blackjack takes half the bet rounded up, so even a single chip can
+	// take it, and is not limited by the bankroll.
+	fn max_insurance(&self, player: usize) -> u64 {
+		let seat = &self.seats[player];
+		if self.blackjacks.contains(&HandId::new(player, 0)) {
+			seat.wagers[0].div_ceil(2)
+		} else {
+			(seat.wagers[0] / 2).min(seat.bankroll)
+		}
+	}
+
+	fn has_dealer_blackjack(&self) -> bool {
+		let mut hand = self.dealer.clone();
+		if let Some(hole) = self.hole {
+			hand.add(hole);
+		}
+
+		hand.cards().len() == 2 && hand.score(&self.score).1 == Some(21)
+	}
+
+	fn settle_insurance(
+		&mut self,
+		dealer_blackjack: bool,
+		events: &mut Vec<Event<'static>>,
+	) {
+		for player in 0..self.seats.len() {
+			let seat = &mut self.seats[player];
+			let insurance = std::mem::take(&mut seat.insurance);
+			if insurance > 0 {
+				// insurance pays 2:1
+				let payout = if dealer_blackjack { insurance * 3 } else { 0 };
+				seat.bankroll += payout;
+				events.push(Event::player(
+					player,
+					0,
+					PlayerEvent::InsurancePayout {
+						wager: insurance,
+						payout,
+						bankroll: seat.bankroll,
+					},
+				));
+			}
+		}
+	}
+
+	// Checks if `decision` is allowed for the hand under the table rules.
+	fn check_decision(
+		&self,
+		player: usize,
+		hand: usize,
+		decision: Decision,
+	) -> Result<(), &'static str> {
+		let seat = &self.seats[player];
+		let current = &seat.hands[hand];
+		let split_aces = self.is_split_aces(player, hand);
+
+		match decision {
+			Decision::Hit if split_aces && !self.rules.hit_split_aces => {
+				Err("Can not hit split aces")
+			}
+			Decision::Hit | Decision::Stand => Ok(()),
+			Decision::DoubleDown => {
+				let score = current.score(&self.score);
+				let total = score.1.unwrap_or(score.0);
+
+				if current.cards().len() != 2 {
+					Err("Can only double down on the first two cards")
+				} else if seat.hands.len() > 1
+					&& !self.rules.double_after_split
+				{
+					Err("Can not double down after a split")
+				} else if split_aces && !self.rules.hit_split_aces {
+					Err("Can not hit split aces")
+				} else if seat.bankroll < seat.wagers[hand] {
+					Err("Insufficient bankroll to double down")
+				} else {
+					match self.rules.double_down {
+						DoubleDownRule::NineToEleven
+							if !(9..=11).contains(&total) =>
+						{
+							Err("Can only double down on a hard 9, 10 or 11")
+						}
+						DoubleDownRule::TenToEleven
+							if !(10..=11).contains(&total) =>
+						{
+							Err("Can only double down on a hard 10 or 11")
+						}
+						_ => Ok(()),
+					}
+				}
+			}
+			Decision::Split => {
+				if !current.is_pair() {
+					Err("Can only split a pair of equal rank")
+				} else if seat.hands.len() >= self.rules.max_split_hands {
+					Err("Can not split into any more hands")
+				} else if split_aces && !self.rules.resplit_aces {
+					Err("Can not re-split aces")
+				} else if seat.bankroll < seat.wagers[hand] {
+					Err("Insufficient bankroll to split")
+				} else {
+					Ok(())
+				}
+			}
+			Decision::Surrender => {
+				if self.rules.surrender == SurrenderRule::None {
+					Err("Surrender is not allowed")
+				} else if current.cards().len() != 2 || seat.hands.len() > 1 {
+					Err("Can only surrender at the start of an unsplit hand")
+				} else {
+					Ok(())
+				}
+			}
+		}
+	}
+
+	// Only splitting aces can produce multiple hands which start with an ace.
+	fn is_split_aces(&self, player: usize, hand: usize) -> bool {
+		let hands = &self.seats[player].hands;
+		hands.len() > 1
+			&& hands[hand].cards().first().map(Card::rank) == Some(&Rank::Ace)
+	}
+
+	fn hand_ids(&self) -> impl Iterator<Item = HandId> + '_ {
+		self.seats.iter().enumerate().flat_map(|(player, seat)| {
+			(0..seat.hands.len()).map(move |hand| HandId::new(player, hand))
+		})
+	}
+
+	fn is_settled(&self, id: HandId) -> bool {
+		self.outcomes.iter().any(|(settled, _)| *settled == id)
+	}
+
+	// Draws the next card. A deck which runs out is refilled with the
+	// discards, like a dealer would.
+	fn draw(
+		&mut self,
+		events: &mut Vec<Event<'static>>,
+	) -> Result<Card, BlackjackError> {
+		if self.deck.is_empty() {
+			self.shuffle_discards(events)?;
+		}
+		self.deck.draw().ok_or(BlackjackError::DeckExhausted)
+	}
+
+	fn shuffle_discards(
+		&mut self,
+		events: &mut Vec<Event<'static>>,
+	) -> Result<(), BlackjackError> {
+		let table = self.cards().copied().collect::<Vec<_>>();
+		let mut rng = StdRng::seed_from_u64(self.seed);
+		self.seed = rng.gen();
+		if !self.deck.reshuffle(&table, &mut rng) {
+			return Err(BlackjackError::DeckExhausted);
+		}
+
+		// the hole card is only seen once it is revealed
+		let mut seen = table;
+		if let Some(hole) = self.hole {
+			if let Some(idx) = seen.iter().position(|card| *card == hole) {
+				seen.remove(idx);
+			}
+		}
+		events.push(Event::dealer(DealerEvent::Shuffle { table: seen }));
+		Ok(())
+	}
+
+	// Hands which are still in the game.
+	fn remaining(&self) -> Vec<HandId> {
+		self.hand_ids().filter(|id| !self.is_settled(*id)).collect()
+	}
+
+	fn draw_player(
+		&mut self,
+		player: usize,
+		hand: usize,
+		events: &mut Vec<Event<'static>>,
+	) -> Result<(), BlackjackError> {
+		let card = self.draw(events)?;
+		events.push(Event::player(player, hand, PlayerEvent::Draw(card)));
+		let current = &mut self.seats[player].hands[hand];
+		current.add(card);
+		events.push(Event::player(
+			player,
+			hand,
+			PlayerEvent::HandChange {
+				hand: Cow::Owned(current.clone()),
+				score: current.score(&self.score),
+			},
+		));
+		Ok(())
+	}
+
+	// With `original_bets_only` losing hands only lose the initial bet, all
+	// wagers added by doubling down or splitting are returned.
+	fn settle(
+		&mut self,
+		original_bets_only: bool,
+		events: &mut Vec<Event<'static>>,
+	) {
+		let mut outcomes = std::mem::take(&mut self.outcomes);
+		outcomes.sort();
+
+		let hands = |filter: &[Outcome]| {
+			outcomes
+				.iter()
+				.filter(|(_, outcome)| filter.contains(outcome))
+				.map(|(id, _)| *id)
+				.collect::<Vec<_>>()
+		};
+		let winners =
+			hands(&[Outcome::Blackjack, Outcome::EvenMoney, Outcome::Win]);
+		let draws = hands(&[Outcome::Push]);
+		let losers = hands(&[Outcome::Lose, Outcome::Surrender]);
+
+		if winners.is_empty() {
+			if draws.is_empty() {
+				events.push(Event::win(WinEvent::Dealer));
+			} else {
+				events.push(Event::win(WinEvent::Push(draws)));
+			}
+		} else {
+			events.push(Event::win(WinEvent::Players(winners)));
+			if !draws.is_empty() {
+				events.push(Event::win(WinEvent::Push(draws)));
+			}
+		}
+		if !losers.is_empty() {
+			events.push(Event::win(WinEvent::Lose(losers)));
+		}
+
+		let payout = self.rules.blackjack_payout;
+		for (id, outcome) in outcomes {
+			let seat = &mut self.seats[id.player];
+			let wager = seat.wagers[id.hand];
+			// fractions of a chip are rounded down in favor of the house
+			let amount = match outcome {
+				Outcome::Blackjack => {
+					wager
+						+ wager * u64::from(payout.win) / u64::from(payout.bet)
+				}
+				Outcome::EvenMoney | Outcome::Win => wager * 2,
+				Outcome::Push => wager,
+				Outcome::Lose if original_bets_only => {
+					if id.hand == 0 {
+						wager - seat.bet
+					} else {
+						wager
+					}
+				}
+				Outcome::Lose => 0,
+				Outcome::Surrender => wager / 2,
+			};
+			seat.bankroll += amount;
+
+			events.push(Event::player(
+				id.player,
+				id.hand,
+				PlayerEvent::Payout {
+					outcome,
+					wager,
+					payout: amount,
+					bankroll: seat.bankroll,
+				},
+			));
+		}
+
+		self.phase = Phase::Settlement;
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use rand::rngs::StdRng;
+	use rand::SeedableRng;
+
+	use super::*;
+	use crate::deck::{Shoe, Stacked};
+
+	// The players get two cards each, then the dealer the upcard and the
+	// hole card.
+	fn game(
+		bankrolls: &[u64],
+		cards: &str,
+	) -> Blackjack<Stacked, BlackjackScore> {
+		game_with(bankrolls, "", cards)
+	}
+
+	// Like `game` with changes to the default rules like "h17,ndas".
+	fn game_with(
+		bankrolls: &[u64],
+		rules: &str,
+		cards: &str,
+	) -> Blackjack<Stacked, BlackjackScore> {
+		Blackjack::with_bankrolls(
+			bankrolls,
+			Stacked::new(cards),
+			rules.parse().unwrap(),
+			&mut StdRng::seed_from_u64(0),
+		)
+	}
+
+	fn illegal(result: Result<Vec<Event<'_>>, BlackjackError>) -> String {
+		match result {
+			Err(BlackjackError::IllegalAction { reason, .. }) => {
+				reason.into_owned()
+			}
+			result => panic!("expected an illegal action, got {:?}", result),
+		}
+	}
+
+	#[test]
+	fn round_goes_through_the_phases() {
+		let mut game = game(&[100, 100], "10h 6c 9d 9s 7h Kc");
+
+		assert_eq!(game.phase(), Phase::Betting { seat: 0 });
+		assert_eq!(game.legal_actions(), vec![Action::Bet(100)]);
+		game.apply(Action::Bet(10)).unwrap();
+		assert_eq!(game.phase(), Phase::Betting { seat: 1 });
+		game.apply(Action::Bet(20)).unwrap();
+		assert_eq!(game.phase(), Phase::Dealing);
+		assert_eq!(game.legal_actions(), vec![Action::Continue]);
+
+		game.apply(Action::Continue).unwrap();
+		assert_eq!(game.phase(), Phase::PlayerTurn { seat: 0, hand: 0 });
+		game.apply(Action::Decision(Decision::Stand)).unwrap();
+		assert_eq!(game.phase(), Phase::PlayerTurn { seat: 1, hand: 0 });
+		game.apply(Action::Decision(Decision::Stand)).unwrap();
+		assert_eq!(game.phase(), Phase::DealerTurn);
+
+		game.apply(Action::Continue).unwrap();
+		assert_eq!(game.phase(), Phase::Settlement);
+		assert!(game.legal_actions().is_empty());
+		// 16 and 18 against 17
+		assert_eq!(game.bankroll(0), Some(90));
+		assert_eq!(game.bankroll(1), Some(120));
+	}
+
+	#[test]
+	fn players_without_a_bet_sit_out() {
+		let mut game = game(&[100, 0, 100], "10h 6c 7h Kc");
+
+		game.apply(Action::Bet(0)).unwrap();
+		assert_eq!(game.phase(), Phase::Betting { seat: 2 });
+		game.apply(Action::Bet(10)).unwrap();
+		game.apply(Action::Continue).unwrap();
+		assert_eq!(game.phase(), Phase::PlayerTurn { seat: 2, hand: 0 });
+		assert!(game.player(0).unwrap().is_empty());
+	}
+
+	#[test]
+	fn ace_upcard_offers_insurance() {
+		let mut game = game(&[100], "10h 6c Ah 7c");
+
+		game.apply(Action::Bet(10)).unwrap();
+		let events = game.apply(Action::Continue).unwrap();
+		assert!(events.contains(&Event::dealer(DealerEvent::OfferInsurance)));
+		assert_eq!(game.phase(), Phase::Insurance { seat: 0 });
+		assert_eq!(game.legal_actions(), vec![Action::Insurance(5)]);
+
+		game.apply(Action::Insurance(5)).unwrap();
+		assert_eq!(game.phase(), Phase::PlayerTurn { seat: 0, hand: 0 });
+		// the insurance is lost once the dealer shows no blackjack
+		assert_eq!(game.bankroll(0), Some(85));
+	}
+
+	#[test]
+	fn even_money_needs_no_stake() {
+		for (bet, max) in [(1, 1), (10, 5), (5, 3)] {
+			let mut game = game(&[bet], "Ac Kd As 5h");
+
+			game.apply(Action::Bet(bet)).unwrap();
+			game.apply(Action::Continue).unwrap();
+			assert_eq!(game.legal_actions(), vec![Action::Insurance(max)]);
+
+			let events = game.apply(Action::Insurance(max)).unwrap();
+			assert!(events.contains(&Event::player(
+				0,
+				0,
+				PlayerEvent::EvenMoney
+			)));
+			assert_eq!(game.phase(), Phase::Settlement);
+			assert_eq!(game.bankroll(0), Some(2 * bet));
+		}
+	}
+
+	#[test]
+	fn insurance_pays_two_to_one_on_a_dealer_blackjack() {
+		let mut game = game(&[100], "Th 6c Ah Kc");
+
+		game.apply(Action::Bet(10)).unwrap();
+		game.apply(Action::Continue).unwrap();
+		let events = game.apply(Action::Insurance(5)).unwrap();
+		assert!(events.contains(&Event::player(
+			0,
+			0,
+			PlayerEvent::InsurancePayout {
+				wager: 5,
+				payout: 15,
+				bankroll: 100
+			}
+		)));
+		assert_eq!(game.phase(), Phase::Settlement);
+		// the insurance makes up for the lost bet
+		assert_eq!(game.bankroll(0), Some(100));
+	}
+
+	#[test]
+	fn declined_even_money_pushes_against_a_dealer_blackjack() {
+		let mut game = game(&[100], "Ah Kc As Kd");
+
+		game.apply(Action::Bet(10)).unwrap();
+		game.apply(Action::Continue).unwrap();
+		game.apply(Action::Insurance(0)).unwrap();
+		assert_eq!(game.phase(), Phase::Settlement);
+		assert_eq!(game.bankroll(0), Some(100));
+	}
+
+	#[test]
+	fn insurance_is_only_offered_against_an_ace() {
+		let mut game = game(&[100], "Th 6c Kh 7c");
+
+		game.apply(Action::Bet(10)).unwrap();
+		let events = game.apply(Action::Continue).unwrap();
+		assert!(!events.contains(&Event::dealer(DealerEvent::OfferInsurance)));
+		assert_eq!(game.phase(), Phase::PlayerTurn { seat: 0, hand: 0 });
+	}
+
+	#[test]
+	fn insurance_of_an_odd_bet_is_rounded_down() {
+		let mut game = game(&[100], "Th 6c Ah 7c");
+
+		game.apply(Action::Bet(5)).unwrap();
+		game.apply(Action::Continue).unwrap();
+		assert_eq!(game.legal_actions(), vec![Action::Insurance(2)]);
+	}
+
+	#[test]
+	fn insurance_is_capped_by_the_bankroll() {
+		let mut game = game(&[12], "10h 6c Ah 7c");
+
+		game.apply(Action::Bet(10)).unwrap();
+		game.apply(Action::Continue).unwrap();
+		assert_eq!(game.legal_actions(), vec![Action::Insurance(2)]);
+		assert_eq!(
+			game.apply(Action::Insurance(3)).unwrap_err(),
+			BlackjackError::InsufficientBankroll { amount: 3, bankroll: 2 }
+		);
+	}
+
+	#[test]
+	fn insurance_is_at_most_half_the_bet() {
+		let mut game = game(&[100], "10h 6c Ah 7c");
+
+		game.apply(Action::Bet(10)).unwrap();
+		game.apply(Action::Continue).unwrap();
+		assert_eq!(
+			illegal(game.apply(Action::Insurance(6))),
+			"Insurance exceeds half the bet"
+		);
+	}
+
+	#[test]
+	fn illegal_actions_leave_the_round_untouched() {
+		let mut game = game(&[100], "10h 6c 7h Kc 2d");
+
+		assert_eq!(
+			illegal(game.apply(Action::Continue)),
+			"Action is not allowed in this phase"
+		);
+		assert_eq!(
+			game.apply(Action::Bet(101)).unwrap_err(),
+			BlackjackError::InsufficientBankroll {
+				amount: 101,
+				bankroll: 100
+			}
+		);
+		assert_eq!(game.phase(), Phase::Betting { seat: 0 });
+
+		game.apply(Action::Bet(10)).unwrap();
+		game.apply(Action::Continue).unwrap();
+		let before = game.clone();
+		assert_eq!(
+			illegal(game.apply(Action::Decision(Decision::Split))),
+			"Can only split a pair of equal rank"
+		);
+		assert_eq!(
+			illegal(game.apply(Action::Insurance(5))),
+			"Action is not allowed in this phase"
+		);
+		assert_eq!(game, before);
+		assert!(!game
+			.legal_actions()
+			.contains(&Action::Decision(Decision::Split)));
+
+		game.apply(Action::Decision(Decision::Hit)).unwrap();
+		assert_eq!(
+			illegal(game.apply(Action::Decision(Decision::DoubleDown))),
+			"Can only double down on the first two cards"
+		);
+	}
+
+	#[test]
+	fn double_down_needs_the_bankroll() {
+		let mut game = game(&[15], "6h 5c 7h Kc");
+
+		game.apply(Action::Bet(10)).unwrap();
+		game.apply(Action::Continue).unwrap();
+		assert_eq!(
+			illegal(game.apply(Action::Decision(Decision::DoubleDown))),
+			"Insufficient bankroll to double down"
+		);
+	}
+
+	#[test]
+	fn split_hands_are_played_in_turn() {
+		let mut game = game(&[100], "8h 8c 7h Kc 3d 10s");
+
+		game.apply(Action::Bet(10)).unwrap();
+		game.apply(Action::Continue).unwrap();
+		game.apply(Action::Decision(Decision::Split)).unwrap();
+		assert_eq!(game.phase(), Phase::PlayerTurn { seat: 0, hand: 0 });
+		assert_eq!(game.bankroll(0), Some(80));
+
+		game.apply(Action::Decision(Decision::Stand)).unwrap();
+		assert_eq!(game.phase(), Phase::PlayerTurn { seat: 0, hand: 1 });
+		assert_eq!(game.hand(0, 1).unwrap().cards().len(), 2);
+		game.apply(Action::Decision(Decision::Stand)).unwrap();
+		assert_eq!(game.phase(), Phase::DealerTurn);
+	}
+
+	#[test]
+	fn split_hands_are_settled_on_their_own() {
+		let mut game = game(&[100], "8h 8c 7h Kc 3d Ts");
+
+		game.apply(Action::Bet(10)).unwrap();
+		game.apply(Action::Continue).unwrap();
+		game.apply(Action::Decision(Decision::Split)).unwrap();
+		game.apply(Action::Decision(Decision::Stand)).unwrap();
+		game.apply(Action::Decision(Decision::Stand)).unwrap();
+		let events = game.apply(Action::Continue).unwrap();
+		// 11 and 18 against 17
+		assert!(events.contains(&Event::win(WinEvent::Players(vec![
+			HandId::new(0, 1)
+		]))));
+		assert!(events
+			.contains(&Event::win(WinEvent::Lose(vec![HandId::new(0, 0)]))));
+		assert_eq!(game.bankroll(0), Some(100));
+	}
+
+	#[test]
+	fn split_aces_get_a_single_card_each() {
+		let mut game = game(&[100], "Ah Ac 7h Kc Td 9s");
+
+		game.apply(Action::Bet(10)).unwrap();
+		game.apply(Action::Continue).unwrap();
+		let events = game.apply(Action::Decision(Decision::Split)).unwrap();
+		assert_eq!(game.phase(), Phase::DealerTurn);
+		assert_eq!(game.hand(0, 0).unwrap().cards().len(), 2);
+		assert_eq!(game.hand(0, 1).unwrap().cards().len(), 2);
+		assert!(!events.iter().any(|event| matches!(
+			event,
+			Event::Player { event: PlayerEvent::Blackjack, .. }
+		)));
+
+		game.apply(Action::Continue).unwrap();
+		// 21 of an ace and a ten pays like any other win
+		assert_eq!(game.bankroll(0), Some(120));
+	}
+
+	#[test]
+	fn dealer_hits_soft_17_by_the_rules() {
+		for (rules, bankroll) in [("s17", 110), ("h17", 90)] {
+			let mut game = game_with(&[100], rules, "Th 8c 6h Ac 2d");
+
+			game.apply(Action::Bet(10)).unwrap();
+			game.apply(Action::Continue).unwrap();
+			game.apply(Action::Decision(Decision::Stand)).unwrap();
+			game.apply(Action::Continue).unwrap();
+			// 18 against a soft 17 or the 19 drawn to it
+			assert_eq!(game.bankroll(0), Some(bankroll), "{}", rules);
+		}
+	}
+
+	#[test]
+	fn rules_restrict_doubling_and_splitting() {
+		let mut game = game_with(&[100], "d10", "6h 3c 7h Kc");
+		game.apply(Action::Bet(10)).unwrap();
+		game.apply(Action::Continue).unwrap();
+		assert_eq!(
+			illegal(game.apply(Action::Decision(Decision::DoubleDown))),
+			"Can only double down on a hard 10 or 11"
+		);
+
+		let mut game = game_with(&[100], "ndas,sp2", "8h 8c 7h Kc 8d");
+		game.apply(Action::Bet(10)).unwrap();
+		game.apply(Action::Continue).unwrap();
+		game.apply(Action::Decision(Decision::Split)).unwrap();
+		assert_eq!(
+			illegal(game.apply(Action::Decision(Decision::DoubleDown))),
+			"Can not double down after a split"
+		);
+		assert_eq!(
+			illegal(game.apply(Action::Decision(Decision::Split))),
+			"Can not split into any more hands"
+		);
+	}
+
+	#[test]
+	fn split_aces_are_played_by_the_rules() {
+		let cards = "Ah Ac 7h Kc Ad 9s";
+		let mut game = game(&[100], cards);
+		game.apply(Action::Bet(10)).unwrap();
+		game.apply(Action::Continue).unwrap();
+		game.apply(Action::Decision(Decision::Split)).unwrap();
+		assert_eq!(game.phase(), Phase::DealerTurn);
+
+		let mut game = game_with(&[100], "rsa", cards);
+		game.apply(Action::Bet(10)).unwrap();
+		game.apply(Action::Continue).unwrap();
+		game.apply(Action::Decision(Decision::Split)).unwrap();
+		assert_eq!(game.phase(), Phase::PlayerTurn { seat: 0, hand: 0 });
+		assert_eq!(
+			game.legal_actions(),
+			vec![
+				Action::Decision(Decision::Stand),
+				Action::Decision(Decision::Split)
+			]
+		);
+
+		let mut game = game_with(&[100], "hsa", cards);
+		game.apply(Action::Bet(10)).unwrap();
+		game.apply(Action::Continue).unwrap();
+		game.apply(Action::Decision(Decision::Split)).unwrap();
+		assert_eq!(game.phase(), Phase::PlayerTurn { seat: 0, hand: 0 });
+		assert!(game
+			.legal_actions()
+			.contains(&Action::Decision(Decision::Hit)));
+	}
+
+	#[test]
+	fn early_surrender_comes_before_the_peek() {
+		let mut game = game_with(&[100], "es", "Th 6c Kh Ac");
+
+		game.apply(Action::Bet(10)).unwrap();
+		game.apply(Action::Continue).unwrap();
+		assert_eq!(game.phase(), Phase::EarlySurrender { seat: 0 });
+		let events =
+			game.apply(Action::Decision(Decision::Surrender)).unwrap();
+		assert!(events.contains(&Event::dealer(DealerEvent::Blackjack)));
+		assert_eq!(game.phase(), Phase::Settlement);
+		assert_eq!(game.bankroll(0), Some(95));
+	}
+
+	#[test]
+	fn blackjack_pays_by_the_rules() {
+		for (rules, bankroll) in [("3:2", 115), ("6:5", 112), ("1:1", 110)] {
+			let mut game = game_with(&[100], rules, "Ah Kc 7h 9c");
+
+			game.apply(Action::Bet(10)).unwrap();
+			game.apply(Action::Continue).unwrap();
+			assert_eq!(game.phase(), Phase::Settlement);
+			assert_eq!(game.bankroll(0), Some(bankroll), "{}", rules);
+		}
+	}
+
+	#[test]
+	fn payouts_carry_the_exact_amounts() {
+		let mut game = game(&[100, 100], "6h 5c Th 6c 7h Kc Td");
+
+		game.apply(Action::Bet(10)).unwrap();
+		game.apply(Action::Bet(10)).unwrap();
+		game.apply(Action::Continue).unwrap();
+		game.apply(Action::Decision(Decision::DoubleDown)).unwrap();
+		assert_eq!(game.wager(0, 0), Some(20));
+		assert_eq!(game.bankroll(0), Some(80));
+		game.apply(Action::Decision(Decision::Surrender)).unwrap();
+
+		let events = game.apply(Action::Continue).unwrap();
+		assert!(events.contains(&Event::player(
+			0,
+			0,
+			PlayerEvent::Payout {
+				outcome: Outcome::Win,
+				wager: 20,
+				payout: 40,
+				bankroll: 120
+			}
+		)));
+		assert!(events.contains(&Event::player(
+			1,
+			0,
+			PlayerEvent::Payout {
+				outcome: Outcome::Surrender,
+				wager: 10,
+				payout: 5,
+				bankroll: 95
+			}
+		)));
+	}
+
+	// Fractions of a chip stay with the house.
+	#[test]
+	fn odd_wagers_are_paid_in_whole_chips() {
+		let mut blackjack = game(&[100], "Ah Kc 7h 9c");
+		blackjack.apply(Action::Bet(5)).unwrap();
+		blackjack.apply(Action::Continue).unwrap();
+		assert_eq!(blackjack.bankroll(0), Some(107));
+
+		let mut surrender = game(&[100], "Th 6c 7h Kc");
+		surrender.apply(Action::Bet(5)).unwrap();
+		surrender.apply(Action::Continue).unwrap();
+		surrender.apply(Action::Decision(Decision::Surrender)).unwrap();
+		assert_eq!(surrender.phase(), Phase::Settlement);
+		assert_eq!(surrender.bankroll(0), Some(97));
+	}
+
+	#[test]
+	fn dealer_blackjack_ends_the_round_after_the_peek() {
+		let mut game = game(&[100], "10h 6c Kh Ac");
+
+		game.apply(Action::Bet(10)).unwrap();
+		let events = game.apply(Action::Continue).unwrap();
+		assert!(events.contains(&Event::dealer(DealerEvent::Peek)));
+		assert!(events.contains(&Event::dealer(DealerEvent::Blackjack)));
+		assert_eq!(game.phase(), Phase::Settlement);
+		assert_eq!(game.bankroll(0), Some(90));
+	}
+
+	#[test]
+	fn hole_card_stays_hidden_until_the_dealer_turn() {
+		let mut game = game(&[100], "Th 7c 9h 8d");
+
+		game.apply(Action::Bet(10)).unwrap();
+		let events = game.apply(Action::Continue).unwrap();
+		assert!(events
+			.contains(&Event::dealer(DealerEvent::Upcard(Card::HEART_NINE))));
+		assert!(!events.iter().any(|event| matches!(
+			event,
+			Event::Dealer(DealerEvent::HoleCard(_))
+		)));
+		assert_eq!(game.dealer().cards().len(), 1);
+
+		game.apply(Action::Decision(Decision::Stand)).unwrap();
+		let events = game.apply(Action::Continue).unwrap();
+		assert!(events.contains(&Event::dealer(DealerEvent::HoleCard(
+			Card::DIAMOND_EIGHT
+		))));
+	}
+
+	// Without a hole card the dealer blackjack only shows after a double
+	// or a split, which are lost too unless only the original bets are.
+	#[test]
+	fn late_dealer_blackjack_takes_doubles_and_splits_unless_obo() {
+		for (rules, bankroll) in [("enhc", 80), ("obo", 90)] {
+			let mut game = game_with(&[100], rules, "6h 5c Kh 9d Ac");
+			game.apply(Action::Bet(10)).unwrap();
+			let events = game.apply(Action::Continue).unwrap();
+			assert!(!events.contains(&Event::dealer(DealerEvent::Peek)));
+			game.apply(Action::Decision(Decision::DoubleDown)).unwrap();
+			let events = game.apply(Action::Continue).unwrap();
+			assert!(events.contains(&Event::dealer(DealerEvent::Blackjack)));
+			assert_eq!(game.bankroll(0), Some(bankroll), "{}", rules);
+
+			let mut game = game_with(&[100], rules, "8h 8c Kh 3d 2s Ac");
+			game.apply(Action::Bet(10)).unwrap();
+			game.apply(Action::Continue).unwrap();
+			game.apply(Action::Decision(Decision::Split)).unwrap();
+			game.apply(Action::Decision(Decision::Stand)).unwrap();
+			game.apply(Action::Decision(Decision::Stand)).unwrap();
+			game.apply(Action::Continue).unwrap();
+			assert_eq!(game.bankroll(0), Some(bankroll), "{} split", rules);
+		}
+	}
+
+	fn replay(seed: u64) -> Vec<Event<'static>> {
+		let mut events = Vec::new();
+		Blackjack::with_players(
+			2,
+			100,
+			Shoe::new(6, 0.75),
+			Rules::default(),
+			&mut StdRng::seed_from_u64(seed),
+		)
+		.run(
+			|_, wager, _| if wager == Wager::Bet { 10 } else { 0 },
+			|player, hand, game| {
+				let score = game.score(player, hand).unwrap();
+				if score.1.unwrap_or(score.0) < 17 {
+					Decision::Hit
+				} else {
+					Decision::Stand
+				}
+			},
+			|event| events.push(event.into_owned()),
+		)
+		.unwrap();
+		events
+	}
+
+	#[test]
+	fn seeded_games_replay_the_same_cards() {
+		assert_eq!(replay(8), replay(8));
+		assert_ne!(replay(8), replay(9));
+	}
+
+	// Errors of the players are reported as events and asked for again.
+	#[test]
+	fn run_asks_again_after_invalid_input() {
+		let mut bets = vec![10, 200];
+		let mut decisions = vec![Decision::Stand, Decision::Split];
+		let mut events = Vec::new();
+		let game = game(&[100], "Th 6c 7h Kc")
+			.run(
+				|_, _, _| bets.pop().unwrap(),
+				|_, _, _| decisions.pop().unwrap(),
+				|event| events.push(event.into_owned()),
+			)
+			.unwrap();
+
+		assert!(events.contains(&Event::player(
+			0,
+			0,
+			PlayerEvent::InvalidBet {
+				amount: 200,
+				reason: "200 exceeds the bankroll of 100".into()
+			}
+		)));
+		assert!(events.contains(&Event::player(
+			0,
+			0,
+			PlayerEvent::InvalidDecision {
+				invalid_decision: Decision::Split,
+				reason: "Can only split a pair of equal rank".into()
+			}
+		)));
+		assert_eq!(game.bankroll(0), Some(90));
+	}
+
+	#[test]
+	fn run_returns_an_exhausted_deck() {
+		let result = game(&[100], "Th 6c 7h").run(
+			|_, _, _| 10,
+			|_, _, _| Decision::Stand,
+			|_| {},
+		);
+		assert_eq!(result.unwrap_err(), BlackjackError::DeckExhausted);
+	}
+
+	#[test]
+	fn empty_deck_is_exhausted() {
+		let mut game = game(&[100], "10h 6c");
+
+		game.apply(Action::Bet(10)).unwrap();
+		assert_eq!(
+			game.apply(Action::Continue).unwrap_err(),
+			BlackjackError::DeckExhausted
+		);
+	}
+}
@@ -0,0 +1,175 @@
+use std::fmt;
+use std::iter::FromIterator;
+use std::str::FromStr;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::card::{Card, ParseCardError};
+use crate::score::Score;
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct Hand {
+	cards: Vec<Card>,
+}
+
+impl Hand {
+	pub fn add(&mut self, card: Card) {
+		self.cards.push(card);
+	}
+
+	pub fn score<S>(&self, score: &S) -> S::Output
+	where
+		S: Score,
+	{
+		score.score(self)
+	}
+
+	pub fn is_bust<S>(&self, score: &S) -> bool
+	where
+		S: Score,
+	{
+		score.is_bust(self)
+	}
+
+	pub fn cards(&self) -> &[Card] {
+		&self.cards
+	}
+
+	pub fn is_pair(&self) -> bool {
+		matches!(self.cards(), [a, b] if a.rank() == b.rank())
+	}
+
+	pub fn split(&mut self) -> Option<Self> {
+		if self.is_pair() {
+			self.cards.pop().map(|card| Self { cards: vec![card] })
+		} else {
+			None
+		}
+	}
+}
+
+impl FromIterator<Card> for Hand {
+	fn from_iter<I: IntoIterator<Item = Card>>(iter: I) -> Self {
+		Self { cards: iter.into_iter().collect() }
+	}
+}
+
+// Cards separated by whitespace or commas, e.g. `AS KD` or `8h, 8d`.
+impl FromStr for Hand {
+	type Err = ParseHandError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		s.split(|c: char| c.is_whitespace() || c == ',')
+			.filter(|card| !card.is_empty())
+			.enumerate()
+			.map(|(position, card)| {
+				card.parse().map_err(|error| ParseHandError {
+					position,
+					card: card.to_owned(),
+					error,
+				})
+			})
+			.collect()
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ParseHandError {
+	// index of the card in the hand
+	pub position: usize,
+	pub card: String,
+	pub error: ParseCardError,
+}
+
+impl fmt::Display for ParseHandError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"Invalid card `{}` at position {}: {}",
+			self.card,
+			self.position + 1,
+			self.error
+		)
+	}
+}
+
+impl std::error::Error for ParseHandError {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		Some(&self.error)
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct HandId {
+	pub player: usize,
+	pub hand: usize,
+}
+
+impl HandId {
+	pub const fn new(player: usize, hand: usize) -> Self {
+		Self { player, hand }
+	}
+}
+
+impl fmt::Display for HandId {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}.{}", self.player, self.hand)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::card::{Rank, Suit};
+
+	#[test]
+	fn parses_cards_separated_by_spaces_or_commas() {
+		let pair =
+			Hand::from_iter(vec![Card::HEART_EIGHT, Card::DIAMOND_EIGHT]);
+		assert_eq!("8h 8d".parse::<Hand>(), Ok(pair.clone()));
+		assert_eq!("8h, 8d".parse::<Hand>(), Ok(pair.clone()));
+		assert_eq!(" 8h,8d ".parse::<Hand>(), Ok(pair));
+		assert_eq!("".parse::<Hand>(), Ok(Hand::default()));
+	}
+
+	#[test]
+	fn glyphs_round_trip() {
+		let hand = "AS 10h Kc".parse::<Hand>().unwrap();
+		let glyphs = hand
+			.cards()
+			.iter()
+			.map(Card::to_string)
+			.collect::<Vec<_>>()
+			.join(" ");
+		assert_eq!(glyphs.parse::<Hand>(), Ok(hand));
+	}
+
+	#[test]
+	fn reports_the_invalid_card() {
+		assert_eq!(
+			"AS 10h Xc".parse::<Hand>(),
+			Err(ParseHandError {
+				position: 2,
+				card: "Xc".to_owned(),
+				error: ParseCardError::InvalidRank("X".to_owned()),
+			})
+		);
+		assert_eq!(
+			"Kz".parse::<Hand>().unwrap_err().error,
+			ParseCardError::InvalidSuit("z".to_owned())
+		);
+	}
+
+	#[test]
+	fn splits_pairs_only() {
+		let mut pair = "8h 8d".parse::<Hand>().unwrap();
+		let split = pair.split().unwrap();
+		assert_eq!(pair.cards(), &[Card::new(Suit::Heart, Rank::Eight)]);
+		assert_eq!(split.cards(), &[Card::DIAMOND_EIGHT]);
+		assert_eq!("8h 9d".parse::<Hand>().unwrap().split(), None);
+	}
+}
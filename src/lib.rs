@@ -0,0 +1,77 @@
+#![allow(rustdoc::private_intra_doc_links)]
+#![deny(
+    // Documentation
+	// TODO: rustdoc::broken_intra_doc_links,
+	// TODO: rustdoc::missing_crate_level_docs,
+	// TODO: missing_docs,
+	// TODO: clippy::missing_docs_in_private_items,
+
+    // Other
+	deprecated_in_future,
+	exported_private_dependencies,
+	future_incompatible,
+	missing_copy_implementations,
+	missing_debug_implementations,
+	private_bounds,
+	private_interfaces,
+	rust_2018_compatibility,
+	rust_2018_idioms,
+	trivial_casts,
+	trivial_numeric_casts,
+	unsafe_code,
+	unstable_features,
+	unused_import_braces,
+	unused_qualifications,
+
+	// clippy attributes
+	clippy::missing_const_for_fn,
+	clippy::redundant_pub_crate,
+	clippy::use_self
+)]
+#![cfg_attr(docsrs, feature(doc_cfg), feature(doc_alias))]
+
+pub mod analysis;
+pub mod card;
+pub mod coach;
+pub mod counting;
+pub mod deck;
+pub mod error;
+pub mod event;
+pub mod game;
+pub mod hand;
+pub mod record;
+pub mod risk;
+pub mod rules;
+pub mod score;
+pub mod simulation;
+pub mod strategy;
+pub mod table;
+pub mod training;
+
+pub use analysis::{Composition, DealerProbabilities, ExpectedValues};
+pub use card::{Card, ParseCardError, Rank, Suit};
+pub use coach::{Coach, Mistake};
+pub use counting::{
+	BetRamp, Counter, CountingPlayer, CountingSystem, IndexPlay,
+	ParseBetRampError, ParseCountingSystemError, Row,
+};
+pub use deck::{ArrayDeck, Deck, Deck52, Shoe, VecDeck, DECK_52};
+pub use error::BlackjackError;
+pub use event::{DealerEvent, Event, Outcome, PlayerEvent, WinEvent};
+pub use game::{Action, Blackjack, Decision, Phase, Wager};
+pub use hand::{Hand, HandId, ParseHandError};
+pub use record::{Record, Recorder};
+pub use risk::{Risk, RiskReport};
+pub use rules::{
+	DoubleDownRule, HoleCardRule, ParseRulesError, Payout, Rules,
+	SurrenderRule,
+};
+pub use score::{BlackjackScore, Score};
+pub use simulation::{Interrupted, Simulation, Statistics};
+pub use strategy::{
+	BasicStrategy, Chart, ParseChartError, ParsePlayError, Play, Strategy,
+};
+pub use table::Table;
+pub use training::{
+	CountQuestion, CountingDrill, Position, StrategyDrill, Tally,
+};
@@ -12,7 +12,8 @@
 	future_incompatible,
 	missing_copy_implementations,
 	missing_debug_implementations,
-	private_in_public,
+	private_bounds,
+	private_interfaces,
 	rust_2018_compatibility,
 	rust_2018_idioms,
 	trivial_casts,
@@ -27,819 +28,773 @@
 	clippy::redundant_pub_crate,
 	clippy::use_self
 )]
-#![cfg_attr(docsrs, feature(doc_cfg), feature(doc_alias))]
 
-use std::borrow::Cow;
-use std::fmt::{self, Write as _};
+use std::cell::RefCell;
 use std::io::Write as _;
-
-use rand::prelude::SliceRandom as _;
-use rand::thread_rng;
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub enum Suit {
-	Club,
-	Diamond,
-	Heart,
-	Spade,
+use std::str::FromStr;
+
+use bjack::{
+	BasicStrategy, BetRamp, BlackjackScore, Card, Chart, Coach, Composition,
+	CountQuestion, Counter, CountingDrill, CountingPlayer, CountingSystem,
+	DealerEvent, DealerProbabilities, Decision, Deck, Event, ExpectedValues,
+	Hand, HandId, IndexPlay, PlayerEvent, Recorder, Risk, Rules, Shoe,
+	Simulation, StrategyDrill, Table, Wager, WinEvent,
+};
+use rand::rngs::StdRng;
+use rand::{thread_rng, Rng, SeedableRng};
+
+fn format_hand(hand: &Hand, score: (u8, Option<u8>)) -> String {
+	format!(
+		"{} (Score: {}{})",
+		hand.cards()
+			.iter()
+			.map(|c| c.to_string())
+			.collect::<Vec<_>>()
+			.join(" "),
+		score.0,
+		score.1.map(|s| format!("/{}", s)).unwrap_or_default()
+	)
 }
 
-impl fmt::Display for Suit {
-	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-		match self {
-			Suit::Club => f.write_str("♣"),
-			Suit::Diamond => f.write_str("♦"),
-			Suit::Heart => f.write_str("♥"),
-			Suit::Spade => f.write_str("♠"),
-		}
-	}
+fn format_hands(hands: &[HandId]) -> String {
+	hands.iter().map(|id| id.to_string()).collect::<Vec<_>>().join(", ")
 }
 
-#[repr(u8)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub enum Rank {
-	Ace,
-	Two,
-	Three,
-	Four,
-	Five,
-	Six,
-	Seven,
-	Eight,
-	Nine,
-	Ten,
-	Jack,
-	Queen,
-	King,
+const USAGE: &str = "\
+Usage: bjack [OPTIONS]
+       bjack simulate [--rounds N] [OPTIONS]
+       bjack risk [--bankroll N] [--sessions N] [OPTIONS]
+       bjack analyze HAND UPCARD [--seen CARDS] [OPTIONS]
+       bjack count [--speed MS] [--every N] [--ask COUNT] [OPTIONS]
+       bjack drill [--questions N] [OPTIONS]
+
+Options:
+  --seed N              replay the cards of a seed
+  --rules RULES         changes to the default rules, e.g. h17,ndas,6:5
+  --decks N             decks in the shoe
+  --chart FILE          play by a strategy chart instead of basic strategy
+  --export-chart FILE   write the strategy chart as CSV and exit
+  --print-chart         print the strategy chart and exit
+  --count SYSTEM        keep the count, e.g. hi-lo, ko, zen or ten tags
+  --coach               point out decisions which differ from the chart
+  --log FILE            write every event as a line of JSON
+
+Simulation options:
+  --rounds N            rounds to simulate
+  --penetration F       fraction of the shoe dealt before shuffling
+  --players N           players at the table
+  --threads N           threads to simulate on, all cores by default
+  --count SYSTEM        count the shoe and bet by the true count
+  --ramp STEPS          units bet from each true count on, e.g. 1:2,2:4,3:8
+  --index-plays LISTS   deviate by the index plays of i18 and fab4
+  --rounds-per-hour N   rounds played in an hour
+
+Risk options:
+  --rounds N            rounds in a session
+  --bankroll N          units each player starts with
+  --sessions N          sessions to simulate
+  --steps N             points of the bankroll trajectories
+  --csv FILE            write the bankroll trajectories as CSV
+
+Analysis options:
+  --seen CARDS          cards already dealt from the shoe, e.g. \"5h 6d\"
+
+Counting drill options:
+  --speed MS            milliseconds each card is shown
+  --every N             cards dealt between questions
+  --ask COUNT           ask for the running, true or both counts
+  --questions N         questions in a session
+
+Strategy drill options:
+  --questions N         positions in a session";
+
+fn usage() -> ! {
+	eprintln!("{}", USAGE);
+	std::process::exit(2)
 }
 
-impl fmt::Display for Rank {
-	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-		match self {
-			Rank::Ace => f.write_str("A"),
-			Rank::Two => f.write_str("2"),
-			Rank::Three => f.write_str("3"),
-			Rank::Four => f.write_str("4"),
-			Rank::Five => f.write_str("5"),
-			Rank::Six => f.write_str("6"),
-			Rank::Seven => f.write_str("7"),
-			Rank::Eight => f.write_str("8"),
-			Rank::Nine => f.write_str("9"),
-			Rank::Ten => f.write_str("10"),
-			Rank::Jack => f.write_str("J"),
-			Rank::Queen => f.write_str("Q"),
-			Rank::King => f.write_str("K"),
-		}
-	}
+// The value of an option, or the usage on a missing or invalid value.
+fn value<T: FromStr>(args: &mut impl Iterator<Item = String>) -> T {
+	args.next().and_then(|value| value.parse().ok()).unwrap_or_else(|| usage())
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct Card(Suit, Rank);
-
-impl Card {
-	pub const CLUB_ACE: Self = Self(Suit::Club, Rank::Ace);
-	pub const CLUB_EIGHT: Self = Self(Suit::Club, Rank::Eight);
-	pub const CLUB_FIVE: Self = Self(Suit::Club, Rank::Five);
-	pub const CLUB_FOUR: Self = Self(Suit::Club, Rank::Four);
-	pub const CLUB_JACK: Self = Self(Suit::Club, Rank::Jack);
-	pub const CLUB_KING: Self = Self(Suit::Club, Rank::King);
-	pub const CLUB_NINE: Self = Self(Suit::Club, Rank::Nine);
-	pub const CLUB_QUEEN: Self = Self(Suit::Club, Rank::Queen);
-	pub const CLUB_SEVEN: Self = Self(Suit::Club, Rank::Seven);
-	pub const CLUB_SIX: Self = Self(Suit::Club, Rank::Six);
-	pub const CLUB_TEN: Self = Self(Suit::Club, Rank::Ten);
-	pub const CLUB_THREE: Self = Self(Suit::Club, Rank::Three);
-	pub const CLUB_TWO: Self = Self(Suit::Club, Rank::Two);
-	pub const DIAMOND_ACE: Self = Self(Suit::Diamond, Rank::Ace);
-	pub const DIAMOND_EIGHT: Self = Self(Suit::Diamond, Rank::Eight);
-	pub const DIAMOND_FIVE: Self = Self(Suit::Diamond, Rank::Five);
-	pub const DIAMOND_FOUR: Self = Self(Suit::Diamond, Rank::Four);
-	pub const DIAMOND_JACK: Self = Self(Suit::Diamond, Rank::Jack);
-	pub const DIAMOND_KING: Self = Self(Suit::Diamond, Rank::King);
-	pub const DIAMOND_NINE: Self = Self(Suit::Diamond, Rank::Nine);
-	pub const DIAMOND_QUEEN: Self = Self(Suit::Diamond, Rank::Queen);
-	pub const DIAMOND_SEVEN: Self = Self(Suit::Diamond, Rank::Seven);
-	pub const DIAMOND_SIX: Self = Self(Suit::Diamond, Rank::Six);
-	pub const DIAMOND_TEN: Self = Self(Suit::Diamond, Rank::Ten);
-	pub const DIAMOND_THREE: Self = Self(Suit::Diamond, Rank::Three);
-	pub const DIAMOND_TWO: Self = Self(Suit::Diamond, Rank::Two);
-	pub const HEART_ACE: Self = Self(Suit::Heart, Rank::Ace);
-	pub const HEART_EIGHT: Self = Self(Suit::Heart, Rank::Eight);
-	pub const HEART_FIVE: Self = Self(Suit::Heart, Rank::Five);
-	pub const HEART_FOUR: Self = Self(Suit::Heart, Rank::Four);
-	pub const HEART_JACK: Self = Self(Suit::Heart, Rank::Jack);
-	pub const HEART_KING: Self = Self(Suit::Heart, Rank::King);
-	pub const HEART_NINE: Self = Self(Suit::Heart, Rank::Nine);
-	pub const HEART_QUEEN: Self = Self(Suit::Heart, Rank::Queen);
-	pub const HEART_SEVEN: Self = Self(Suit::Heart, Rank::Seven);
-	pub const HEART_SIX: Self = Self(Suit::Heart, Rank::Six);
-	pub const HEART_TEN: Self = Self(Suit::Heart, Rank::Ten);
-	pub const HEART_THREE: Self = Self(Suit::Heart, Rank::Three);
-	pub const HEART_TWO: Self = Self(Suit::Heart, Rank::Two);
-	pub const SPADE_ACE: Self = Self(Suit::Spade, Rank::Ace);
-	pub const SPADE_EIGHT: Self = Self(Suit::Spade, Rank::Eight);
-	pub const SPADE_FIVE: Self = Self(Suit::Spade, Rank::Five);
-	pub const SPADE_FOUR: Self = Self(Suit::Spade, Rank::Four);
-	pub const SPADE_JACK: Self = Self(Suit::Spade, Rank::Jack);
-	pub const SPADE_KING: Self = Self(Suit::Spade, Rank::King);
-	pub const SPADE_NINE: Self = Self(Suit::Spade, Rank::Nine);
-	pub const SPADE_QUEEN: Self = Self(Suit::Spade, Rank::Queen);
-	pub const SPADE_SEVEN: Self = Self(Suit::Spade, Rank::Seven);
-	pub const SPADE_SIX: Self = Self(Suit::Spade, Rank::Six);
-	pub const SPADE_TEN: Self = Self(Suit::Spade, Rank::Ten);
-	pub const SPADE_THREE: Self = Self(Suit::Spade, Rank::Three);
-	pub const SPADE_TWO: Self = Self(Suit::Spade, Rank::Two);
-
-	pub const fn suit(&self) -> &Suit {
-		&self.0
-	}
-
-	pub const fn rank(&self) -> &Rank {
-		&self.1
+// The decision for the letters of the options listed to the player.
+fn decision(input: &str) -> Option<Decision> {
+	match input.trim().to_ascii_lowercase().as_str() {
+		"h" => Some(Decision::Hit),
+		"s" => Some(Decision::Stand),
+		"d" => Some(Decision::DoubleDown),
+		"p" => Some(Decision::Split),
+		"r" => Some(Decision::Surrender),
+		_ => None,
 	}
 }
 
-impl fmt::Display for Card {
-	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-		let base = match self.suit() {
-			Suit::Club => '\u{1F0A1}',
-			Suit::Diamond => '\u{1F0B1}',
-			Suit::Heart => '\u{1F0C1}',
-			Suit::Spade => '\u{1F0D1}',
-		};
+type Log = Box<dyn FnMut(&Event<'_>)>;
 
-		let mut add = *self.rank() as u8 as u32;
-		if self.rank() > &Rank::Jack {
-			add += 1;
-		}
+// Writes every event with the table it happened at as a line of JSON.
+fn logger(file: &str) -> impl FnMut(&Event<'_>) {
+	let file = std::fs::File::create(file)
+		.unwrap_or_else(|error| panic!("Can not write {}: {}", file, error));
+	let mut writer = std::io::LineWriter::new(file);
+	let mut recorder = Recorder::new();
 
-		f.write_char(char::from_u32(base as u32 + add).unwrap())
+	move |event| {
+		let record = recorder.record(event);
+		writeln!(writer, "{}", serde_json::to_string(&record).unwrap())
+			.unwrap();
 	}
 }
 
-pub trait Deck {
-	fn shuffle(&mut self);
-
-	fn draw(&mut self) -> Option<Card>;
-
-	fn len(&self) -> usize;
-
-	fn is_empty(&self) -> bool {
-		self.len() == 0
-	}
+fn load_chart(file: &str) -> Chart {
+	let text = std::fs::read_to_string(file)
+		.unwrap_or_else(|error| panic!("Can not read {}: {}", file, error));
+	text.parse()
+		.unwrap_or_else(|error| panic!("Invalid chart {}: {}", file, error))
 }
 
-pub type Deck52 = ArrayDeck<52>;
-#[rustfmt::skip]
-pub const DECK_52: Deck52 = Deck52::new([
-    Card::CLUB_ACE, Card::CLUB_TWO, Card::CLUB_THREE, Card::CLUB_FOUR,
-    Card::CLUB_FIVE, Card::CLUB_SIX, Card::CLUB_SEVEN, Card::CLUB_EIGHT,
-    Card::CLUB_NINE, Card::CLUB_TEN, Card::CLUB_JACK, Card::CLUB_QUEEN,
-    Card::CLUB_KING,
-    Card::DIAMOND_ACE, Card::DIAMOND_TWO, Card::DIAMOND_THREE,
-    Card::DIAMOND_FOUR, Card::DIAMOND_FIVE, Card::DIAMOND_SIX,
-    Card::DIAMOND_SEVEN, Card::DIAMOND_EIGHT, Card::DIAMOND_NINE,
-    Card::DIAMOND_TEN, Card::DIAMOND_JACK, Card::DIAMOND_QUEEN,
-    Card::DIAMOND_KING,
-    Card::HEART_ACE, Card::HEART_TWO, Card::HEART_THREE, Card::HEART_FOUR,
-    Card::HEART_FIVE, Card::HEART_SIX, Card::HEART_SEVEN, Card::HEART_EIGHT,
-    Card::HEART_NINE, Card::HEART_TEN, Card::HEART_JACK, Card::HEART_QUEEN,
-    Card::HEART_KING,
-    Card::SPADE_ACE, Card::SPADE_TWO, Card::SPADE_THREE, Card::SPADE_FOUR,
-    Card::SPADE_FIVE, Card::SPADE_SIX, Card::SPADE_SEVEN, Card::SPADE_EIGHT,
-    Card::SPADE_NINE, Card::SPADE_TEN, Card::SPADE_JACK, Card::SPADE_QUEEN,
-    Card::SPADE_KING,
-]);
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
-pub struct ArrayDeck<const SIZE: usize> {
-	// index on which the last valid card resides. All cards 0..valid_idx should
-	// be valid.
-	valid_idx: usize,
-	cards: [Card; SIZE],
-}
-
-impl<const SIZE: usize> ArrayDeck<SIZE> {
-	pub const fn new(cards: [Card; SIZE]) -> Self {
-		Self { valid_idx: SIZE, cards }
-	}
-
-	pub fn cards(&self) -> Iter<'_> {
-		Iter::new(&self.cards[..self.valid_idx])
+fn main() {
+	let mut args = std::env::args().skip(1).peekable();
+	match args.peek().map(String::as_str) {
+		Some("simulate") => {
+			args.next();
+			simulate(args, false);
+		}
+		Some("risk") => {
+			args.next();
+			simulate(args, true);
+		}
+		Some("analyze") => {
+			args.next();
+			analyze(args);
+		}
+		Some("drill") => {
+			args.next();
+			drill(args);
+		}
+		Some("count") => {
+			args.next();
+			count(args);
+		}
+		_ => play(args),
 	}
 }
 
-impl<const SIZE: usize> Deck for ArrayDeck<SIZE> {
-	fn shuffle(&mut self) {
-		(&mut self.cards[..self.valid_idx]).shuffle(&mut thread_rng())
-	}
-
-	fn draw(&mut self) -> Option<Card> {
-		if self.is_empty() {
-			None
-		} else {
-			self.valid_idx -= 1;
-			Some(self.cards[self.valid_idx])
+// Simulates many rounds, or with `risk` many sessions of a few rounds.
+fn simulate(mut args: impl Iterator<Item = String>, risk: bool) {
+	let mut rounds = if risk { 10_000 } else { 1_000_000 };
+	let mut bankroll = 1000;
+	let mut sessions = 1000;
+	let mut steps = 20;
+	let mut csv_file = None;
+	let mut seed = None;
+	let mut threads =
+		std::thread::available_parallelism().map_or(1, usize::from);
+	let mut chart_file = None;
+	let mut system = None;
+	let mut ramp = None;
+	let mut index_plays = None;
+	let mut rounds_per_hour = 100.0;
+	let mut simulation = Simulation::new(Rules::default(), 6);
+
+	while let Some(arg) = args.next() {
+		match arg.as_str() {
+			"--rounds" => rounds = value(&mut args),
+			"--seed" => seed = Some(value(&mut args)),
+			"--rules" => simulation.rules = value(&mut args),
+			"--decks" => simulation.decks = value(&mut args),
+			"--penetration" => simulation.penetration = value(&mut args),
+			"--players" => simulation.players = value(&mut args),
+			"--threads" => threads = value(&mut args),
+			"--chart" => chart_file = Some(value::<String>(&mut args)),
+			"--count" => system = Some(value::<CountingSystem>(&mut args)),
+			"--ramp" => ramp = Some(value(&mut args)),
+			"--index-plays" => {
+				let index_plays = index_plays.get_or_insert_with(Vec::new);
+				for name in value::<String>(&mut args).split(',') {
+					match name.trim().to_ascii_lowercase().as_str() {
+						"i18" => index_plays
+							.extend_from_slice(&IndexPlay::ILLUSTRIOUS_18),
+						"fab4" => {
+							index_plays.extend_from_slice(&IndexPlay::FAB_4)
+						}
+						_ => usage(),
+					}
+				}
+			}
+			"--rounds-per-hour" => rounds_per_hour = value(&mut args),
+			"--bankroll" if risk => bankroll = value(&mut args),
+			"--sessions" if risk => sessions = value(&mut args),
+			"--steps" if risk => steps = value(&mut args),
+			"--csv" if risk => csv_file = Some(value::<String>(&mut args)),
+			_ => usage(),
 		}
 	}
+	simulation.counting = match (system, ramp, index_plays) {
+		(Some(system), ramp, index_plays) => Some(CountingPlayer {
+			system,
+			ramp: ramp.unwrap_or_else(|| BetRamp::flat(1)),
+			index_plays: index_plays.unwrap_or_default(),
+		}),
+		(None, None, None) => None,
+		(None, ..) => {
+			eprintln!("!! --ramp and --index-plays need --count");
+			usage();
+		}
+	};
+	if simulation.decks == 0
+		|| simulation.players == 0
+		|| threads == 0
+		|| !(0.0..=1.0).contains(&simulation.penetration)
+	{
+		usage();
+	}
+
+	let chart = match chart_file {
+		Some(file) => load_chart(&file),
+		None => BasicStrategy::new(simulation.decks).chart(&simulation.rules),
+	};
+	let seed = seed.unwrap_or_else(|| thread_rng().gen());
+
+	println!(">> Seed {}", seed);
+	println!(">> Rules {}", simulation.rules);
+	println!(
+		">> {} decks, {:.0}% penetration, {} players, {} threads",
+		simulation.decks,
+		simulation.penetration * 100.0,
+		simulation.players,
+		threads
+	);
 
-	fn len(&self) -> usize {
-		self.valid_idx
+	if let Some(counting) = &simulation.counting {
+		println!(
+			">> Counting with tags {:?}, ramp {}, {} index plays",
+			counting.system.tags,
+			counting.ramp,
+			counting.index_plays.len()
+		);
 	}
-}
-
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub struct VecDeck(Vec<Card>);
 
-impl VecDeck {
-	pub const fn new(cards: Vec<Card>) -> Self {
-		Self(cards)
-	}
+	if risk {
+		if bankroll == 0 {
+			usage();
+		}
+		let risk = Risk { rounds, steps, ..Risk::new(simulation, bankroll) };
+		// the sessions played before an error still count
+		let report = risk
+			.run_parallel(sessions, threads, &chart, seed)
+			.unwrap_or_else(|interrupted| {
+				println!("!! {}", interrupted);
+				*interrupted.partial
+			});
+		println!("{}", report);
+		if let Some(file) = csv_file {
+			std::fs::write(&file, report.to_csv()).unwrap_or_else(|error| {
+				panic!("Can not write {}: {}", file, error)
+			});
+			println!(">> Trajectories written to {}", file);
+		}
+		return;
+	}
+
+	// the rounds played before an error still count
+	let statistics = simulation
+		.run_parallel(rounds, threads, &chart, seed)
+		.unwrap_or_else(|interrupted| {
+			println!("!! {}", interrupted);
+			*interrupted.partial
+		});
+	println!("{}", statistics);
+	let (ev, std_dev) = statistics.over(rounds_per_hour);
+	println!(
+		"Hourly EV     {:>+12.4} units, std. dev. {:.4} at {} rounds",
+		ev, std_dev, rounds_per_hour
+	);
 }
 
-impl Deck for VecDeck {
-	fn shuffle(&mut self) {
-		self.0.shuffle(&mut thread_rng());
-	}
-
-	fn draw(&mut self) -> Option<Card> {
-		self.0.pop()
+fn analyze(mut args: impl Iterator<Item = String>) {
+	let hand: Hand = value(&mut args);
+	let upcard: Card = value(&mut args);
+	let mut seen = Hand::default();
+	let mut rules = Rules::default();
+	let mut decks = 6;
+
+	while let Some(arg) = args.next() {
+		match arg.as_str() {
+			"--seen" => seen = value(&mut args),
+			"--rules" => rules = value(&mut args),
+			"--decks" => decks = value(&mut args),
+			_ => usage(),
+		}
 	}
 
-	fn len(&self) -> usize {
-		self.0.len()
+	let mut shoe = Composition::decks(decks);
+	for card in hand.cards().iter().chain(&[upcard]).chain(seen.cards()) {
+		if !shoe.remove(card) {
+			eprintln!("!! No {} left in {} decks", card, decks);
+			std::process::exit(2);
+		}
 	}
-}
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub struct Iter<'a> {
-	idx: usize,
-	cards: &'a [Card],
+	println!(
+		">> Hand {} against {}",
+		format_hand(&hand, hand.score(&BlackjackScore)),
+		upcard
+	);
+	println!(">> Rules {}, {} decks, {} cards left", rules, decks, shoe.len());
+	println!(
+		">> Dealer {:>8}{:>9}{:>9}{:>9}{:>9}{:>9}{:>9}",
+		"17", "18", "19", "20", "21", "BJ", "Bust"
+	);
+	println!(">>       {}", DealerProbabilities::new(&upcard, &shoe, &rules));
+	print!("{}", ExpectedValues::new(&hand, &upcard, &shoe, &rules));
 }
 
-impl<'a> Iter<'a> {
-	pub const fn new(cards: &'a [Card]) -> Self {
-		Self { idx: 0, cards }
+fn drill(mut args: impl Iterator<Item = String>) {
+	let mut seed = None;
+	let mut rules = Rules::default();
+	let mut decks = 6;
+	let mut chart_file = None;
+	let mut questions = 20;
+
+	while let Some(arg) = args.next() {
+		match arg.as_str() {
+			"--seed" => seed = Some(value(&mut args)),
+			"--rules" => rules = value(&mut args),
+			"--decks" => decks = value(&mut args),
+			"--chart" => chart_file = Some(value::<String>(&mut args)),
+			"--questions" => questions = value(&mut args),
+			_ => usage(),
+		}
 	}
-}
+	if decks == 0 {
+		usage();
+	}
+
+	let chart = match chart_file {
+		Some(file) => load_chart(&file),
+		None => BasicStrategy::new(decks).chart(&rules),
+	};
+	let seed = seed.unwrap_or_else(|| thread_rng().gen());
+	println!(">> Seed {}", seed);
+	println!(">> Rules {}, {} decks", rules, decks);
+	let mut drill = StrategyDrill::with_rng(
+		rules,
+		chart,
+		decks,
+		StdRng::seed_from_u64(seed),
+	);
 
-impl<'a> Iterator for Iter<'a> {
-	type Item = &'a Card;
+	let mut buf = String::new();
+	'session: for _ in 0..questions {
+		let position = match drill.position() {
+			Some(position) => position,
+			None => break,
+		};
+		println!(
+			">> Dealer shows {}, you have {}",
+			position.upcard,
+			format_hand(&position.hand, position.hand.score(&BlackjackScore))
+		);
+
+		let (decision, time) = loop {
+			println!(
+				"Options [H]it, [S]tand, [D]oubleDown, S[P]lit, Su[R]render"
+			);
+			print!(">> Choose: ");
+			std::io::stdout().flush().unwrap();
+
+			let start = std::time::Instant::now();
+			buf.clear();
+			if std::io::stdin().read_line(&mut buf).unwrap() == 0 {
+				println!();
+				break 'session;
+			}
+			if let Some(decision) = decision(&buf) {
+				break (decision, start.elapsed());
+			}
+		};
 
-	fn next(&mut self) -> Option<Self::Item> {
-		if self.idx == self.cards.len() {
-			None
+		if drill.answer(&position, decision, time) {
+			println!(">> Correct");
 		} else {
-			let card = &self.cards[self.idx];
-			self.idx += 1;
-			Some(card)
+			println!(
+				">> Wrong, {} is played {} against a {}",
+				position.row(),
+				drill.correct(&position),
+				position.upcard
+			);
 		}
 	}
-}
-
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub enum Decision {
-	Hit,
-	Stand,
-	DoubleDown,
-	Split,
-	Surrender,
-}
 
-impl fmt::Display for Decision {
-	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-		// TODO: real
-		fmt::Debug::fmt(&self, f)
+	println!(">> {}", drill.total());
+	for (row, tally) in drill.tallies() {
+		if tally.mistakes() > 0 {
+			println!(">> {:<14} {}", row.to_string(), tally);
+		}
 	}
 }
 
-pub trait Score {
-	type Output;
-
-	fn score(&self, hand: &Hand) -> Self::Output;
-	fn is_bust(&self, hand: &Hand) -> bool;
-}
+fn count(mut args: impl Iterator<Item = String>) {
+	let mut seed = None;
+	let mut decks = 6;
+	let mut system = CountingSystem::HI_LO;
+	let mut speed = 1000;
+	let mut every = 10;
+	let mut ask = String::from("running");
+	let mut questions = 10;
+
+	while let Some(arg) = args.next() {
+		match arg.as_str() {
+			"--seed" => seed = Some(value(&mut args)),
+			"--decks" => decks = value(&mut args),
+			"--count" => system = value(&mut args),
+			"--speed" => speed = value(&mut args),
+			"--every" => every = value(&mut args),
+			"--ask" => ask = value(&mut args),
+			"--questions" => questions = value(&mut args),
+			_ => usage(),
+		}
+	}
+	let asked = match ask.as_str() {
+		"running" => vec![CountQuestion::RunningCount],
+		"true" => vec![CountQuestion::TrueCount],
+		"both" => vec![CountQuestion::RunningCount, CountQuestion::TrueCount],
+		_ => usage(),
+	};
+	if decks == 0 || every == 0 {
+		usage();
+	}
+
+	let seed = seed.unwrap_or_else(|| thread_rng().gen());
+	println!(">> Seed {}", seed);
+	let mut rng = StdRng::seed_from_u64(seed);
+	let mut drill = CountingDrill::with_rng(
+		system,
+		decks,
+		0.75,
+		StdRng::seed_from_u64(rng.gen()),
+	);
+	println!(
+		">> {} decks, the count starts at {:+}",
+		decks,
+		drill.counter().running_count()
+	);
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct BlackjackScore;
+	let delay = std::time::Duration::from_millis(speed);
+	let mut buf = String::new();
+	'session: for _ in 0..questions {
+		for _ in 0..every {
+			if drill.shoe().needs_shuffle() {
+				println!("\r>> Shuffle, the count starts over");
+			}
+			if let Some(card) = drill.deal() {
+				// every card replaces the one before
+				print!("\r>> {}  ", card);
+				std::io::stdout().flush().unwrap();
+				std::thread::sleep(delay);
+			}
+		}
 
-impl Score for BlackjackScore {
-	type Output = (u8, Option<u8>);
+		let question = asked[rng.gen_range(0..asked.len())];
+		let answer = loop {
+			print!("\r>> The {}? ", question);
+			std::io::stdout().flush().unwrap();
 
-	fn score(&self, hand: &Hand) -> Self::Output {
-		let score =
-			hand.cards().iter().map(|c| ((*c.rank() as u8) + 1).min(10)).sum();
+			let start = std::time::Instant::now();
+			buf.clear();
+			if std::io::stdin().read_line(&mut buf).unwrap() == 0 {
+				println!();
+				break 'session;
+			}
+			if let Ok(answer) = buf.trim().parse::<f64>() {
+				break (answer, start.elapsed());
+			}
+		};
 
-		if score <= 11 && hand.cards().iter().any(|c| c.rank() == &Rank::Ace) {
-			(score, Some(score + 10))
+		if drill.answer(question, answer.0, answer.1) {
+			println!(">> Correct");
 		} else {
-			(score, None)
+			println!(
+				">> Wrong, the {} is {:+}",
+				question,
+				drill.expected(question)
+			);
 		}
 	}
 
-	fn is_bust(&self, hand: &Hand) -> bool {
-		self.score(hand).0 > 21
+	for question in asked {
+		println!(">> {:<14} {}", question.to_string(), drill.tally(question));
 	}
 }
 
-#[derive(Default, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct Hand {
-	cards: Vec<Card>,
-}
-
-impl Hand {
-	pub fn add(&mut self, card: Card) {
-		self.cards.push(card);
-	}
-
-	pub fn score<S>(&self, score: &S) -> S::Output
-	where
-		S: Score,
-	{
-		score.score(self)
-	}
-
-	pub fn is_bust<S>(&self, score: &S) -> bool
-	where
-		S: Score,
-	{
-		score.is_bust(self)
+// Ends the session in the middle of a round when the input is closed, the
+// hands of the round are left unplayed.
+fn end_session(coach: &RefCell<Option<Coach>>) -> ! {
+	println!();
+	if let Some(coach) = &*coach.borrow() {
+		println!(">> Coach: {}", coach);
 	}
-
-	pub fn cards(&self) -> &[Card] {
-		&self.cards
-	}
-}
-
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub enum Event<'a> {
-	Player { player: usize, event: PlayerEvent<'a> },
-	Dealer(DealerEvent<'a>),
-	Win(WinEvent),
+	std::process::exit(0)
 }
 
-impl<'a> Event<'a> {
-	pub const fn player(player: usize, event: PlayerEvent<'a>) -> Self {
-		Self::Player { player, event }
-	}
-
-	pub const fn dealer(event: DealerEvent<'a>) -> Self {
-		Self::Dealer(event)
-	}
-
-	pub const fn win(event: WinEvent) -> Self {
-		Self::Win(event)
+fn play(mut args: impl Iterator<Item = String>) {
+	let mut seed = None;
+	let mut rules = Rules::default();
+	let mut decks = 6;
+	let mut chart_file = None;
+	let mut export_file = None;
+	let mut print_chart = false;
+	let mut system = None;
+	let mut coached = false;
+	let mut log: Option<Log> = None;
+
+	while let Some(arg) = args.next() {
+		match arg.as_str() {
+			// the same seed replays the same cards
+			"--seed" => seed = Some(value(&mut args)),
+			"--rules" => rules = value(&mut args),
+			"--decks" => decks = value(&mut args),
+			"--chart" => chart_file = Some(value::<String>(&mut args)),
+			"--export-chart" => export_file = Some(value::<String>(&mut args)),
+			"--print-chart" => print_chart = true,
+			"--count" => system = Some(value::<CountingSystem>(&mut args)),
+			"--coach" => coached = true,
+			"--log" => {
+				log = Some(Box::new(logger(&value::<String>(&mut args))))
+			}
+			_ => usage(),
+		}
 	}
-}
-
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub enum PlayerEvent<'a> {
-	TurnStart,
-	TurnEnd,
-	ChooseDecision(Decision),
-	ExecuteDecision(Decision),
-	InvalidDecision { invalid_decision: Decision, reason: Cow<'static, str> },
-	Draw(Card),
-	InitialHand { hand: &'a Hand, score: (u8, Option<u8>) },
-	HandChange { hand: &'a Hand, score: (u8, Option<u8>) },
-	Bust { score: u8 },
-	Surrender,
-	Blackjack,
-}
-
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub enum DealerEvent<'a> {
-	TurnStart,
-	TurnEnd,
-	Decision(Decision),
-	Draw(Card),
-	InitialHand { hand: &'a Hand, score: (u8, Option<u8>) },
-	HandChange { hand: &'a Hand, score: (u8, Option<u8>) },
-	Bust { score: u8 },
-	Blackjack,
-}
-
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub enum WinEvent {
-	Dealer,
-	Players(Vec<usize>),
-	Push(Vec<usize>),
-	Lose(Vec<usize>),
-}
-
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
-pub struct Blackjack<D, S> {
-	dealer: Hand,
-	hands: Vec<Hand>,
-	deck: D,
-	score: S,
-}
-
-impl<D> Blackjack<D, BlackjackScore>
-where
-	D: Deck,
-{
-	pub fn with_players(players: usize, deck: D) -> Self {
-		Self::with_hands(Hand::default(), vec![Hand::default(); players], deck)
+	if decks == 0 {
+		usage();
 	}
 
-	pub fn with_hands(dealer: Hand, hands: Vec<Hand>, mut deck: D) -> Self {
-		deck.shuffle();
+	// a house chart replaces the basic strategy of the rules for the hints
+	let chart = match chart_file {
+		Some(file) => load_chart(&file),
+		None => BasicStrategy::new(decks).chart(&rules),
+	};
 
-		Self { dealer, hands, deck, score: BlackjackScore }
+	if let Some(file) = &export_file {
+		std::fs::write(file, chart.to_csv()).unwrap_or_else(|error| {
+			panic!("Can not write {}: {}", file, error)
+		});
 	}
-
-	pub fn dealer(&self) -> &Hand {
-		&self.dealer
+	if print_chart {
+		print!("{}", chart);
 	}
-
-	pub fn player(&self, player: usize) -> Option<&Hand> {
-		self.hands.get(player)
+	if export_file.is_some() || print_chart {
+		return;
 	}
 
-	pub fn score(
-		&self,
-		player: usize,
-	) -> Option<<BlackjackScore as Score>::Output> {
-		self.hands.get(player).map(|hand| hand.score(&self.score))
-	}
+	let seed = seed.unwrap_or_else(|| thread_rng().gen());
+	println!(">> Seed {}", seed);
 
-	pub fn players(&self) -> usize {
-		self.hands.len()
+	let mut table = Table::with_rng(
+		4,
+		Shoe::new(decks, 0.75),
+		rules,
+		StdRng::seed_from_u64(seed),
+	);
+	while table.join(100).is_some() {}
+	let mut coach = None;
+	if coached {
+		table.set_coach(Some(chart.clone()));
+		coach = Some(Coach::new(rules, decks));
 	}
+	let coach = RefCell::new(coach);
 
-	pub fn run<I, E>(mut self, mut input: I, mut event: E)
-	where
-		I: FnMut(usize, &Self) -> Decision,
-		E: for<'a> FnMut(Event<'a>),
-	{
-		let mut out_of_the_game = Vec::with_capacity(self.hands.len());
-
-		// initial draws
-		// TODO: maybe first 1 for each and then give second round
-		for player in 0..self.hands.len() {
-			let card1 = self.deck.draw().unwrap();
-			let card2 = self.deck.draw().unwrap();
-			let hand = &mut self.hands[player];
-			hand.add(card1);
-			hand.add(card2);
-			event(Event::player(
-				player,
-				PlayerEvent::InitialHand {
-					hand,
-					score: hand.score(&self.score),
-				},
-			));
-		}
-
-		let card1 = self.deck.draw().unwrap();
-		let card2 = self.deck.draw().unwrap();
-		let hand = &mut self.dealer;
-		hand.add(card1);
-		hand.add(card2);
-		event(Event::dealer(DealerEvent::InitialHand {
-			hand,
-			score: hand.score(&self.score),
-		}));
-
-		// check blackjack
-		let player_blackjacks = (0..self.hands.len())
-			.filter(|&player| {
-				self.hands[player].score(&self.score).1 == Some(21)
-			})
-			.collect::<Vec<_>>();
-
-		for player in &player_blackjacks {
-			event(Event::player(*player, PlayerEvent::Blackjack));
-		}
-
-		let dealer_blackjack = self.dealer.score(&self.score).1 == Some(21);
-
-		if dealer_blackjack {
-			event(Event::dealer(DealerEvent::Blackjack));
-		}
-
-		if player_blackjacks.is_empty() {
-			if dealer_blackjack {
-				event(Event::win(WinEvent::Dealer));
-				event(Event::win(WinEvent::Lose(
-					(0..self.hands.len()).collect(),
-				)));
-				return;
-			}
-		} else {
-			let losers = (0..self.hands.len())
-				.filter(|player| !player_blackjacks.contains(player))
-				.collect();
-
-			let win_event = if dealer_blackjack {
-				WinEvent::Push(player_blackjacks)
-			} else {
-				WinEvent::Players(player_blackjacks)
-			};
-
-			event(Event::win(win_event));
-			event(Event::win(WinEvent::Lose(losers)));
-			return;
-		}
+	let mut last_hand = None;
+	// the event printer counts while the prompts read the count
+	let counter =
+		RefCell::new(system.map(|system| Counter::new(system, decks)));
 
-		// player
-		for player in 0..self.hands.len() {
-			let mut decisions = 0;
-
-			event(Event::player(player, PlayerEvent::TurnStart));
-
-			while !self.hands[player].is_bust(&self.score) {
-				let decision = input(player, &self);
-				decisions += 1;
-				event(Event::player(
-					player,
-					PlayerEvent::ChooseDecision(decision),
-				));
-
-				match decision {
-					Decision::Hit => {
-						event(Event::player(
-							player,
-							PlayerEvent::ExecuteDecision(decision),
-						));
-						let card = self.deck.draw().unwrap();
-						event(Event::player(player, PlayerEvent::Draw(card)));
-						let hand = &mut self.hands[player];
-						hand.add(card);
-						event(Event::player(
-							player,
-							PlayerEvent::HandChange {
-								hand,
-								score: hand.score(&self.score),
-							},
-						));
-					}
-					Decision::Stand => {
-						event(Event::player(
-							player,
-							PlayerEvent::ExecuteDecision(decision),
-						));
-						break;
-					}
-					Decision::DoubleDown => {
-						event(Event::player(
-							player,
-							PlayerEvent::ExecuteDecision(decision),
-						));
-						let card = self.deck.draw().unwrap();
-						event(Event::player(player, PlayerEvent::Draw(card)));
-						let hand = &mut self.hands[player];
-						hand.add(card);
-						event(Event::player(
-							player,
-							PlayerEvent::HandChange {
-								hand,
-								score: hand.score(&self.score),
-							},
-						));
-						break;
-					}
-					Decision::Split => todo!(),
-					Decision::Surrender => {
-						if decisions == 1 {
-							event(Event::player(
-								player,
-								PlayerEvent::ExecuteDecision(decision),
-							));
-							Event::player(player, PlayerEvent::Surrender);
-							out_of_the_game.push(player);
-							break;
-						} else {
-							event(Event::player(
-								player,
-								PlayerEvent::InvalidDecision {
-									invalid_decision: decision,
-									reason: Cow::Borrowed(
-										"Can only surrender at the start of \
-										 a game",
-									),
-								},
-							));
+	// TODO: cleanup
+	let session = table.run(
+		|player, wager, game| {
+			let mut buf = String::new();
+			loop {
+				match wager {
+					Wager::Bet => print!(
+						">> Player {} bet (bankroll {}): ",
+						player,
+						game.bankroll(player).unwrap()
+					),
+					Wager::Insurance { max } => {
+						if let Some(counter) = &*counter.borrow() {
+							println!(
+								">> True count {:+.1}, insurance {}",
+								counter.true_count(),
+								if counter.insurance() {
+									"pays"
+								} else {
+									"does not pay"
+								}
+							);
 						}
+						print!(
+							">> Player {} insurance (max {}, 0 to decline): ",
+							player, max
+						)
 					}
 				}
-			}
-
-			if self.hands[player].is_bust(&self.score) {
-				let score = self.hands[player].score(&self.score);
-				event(Event::player(
-					player,
-					PlayerEvent::Bust { score: score.0 },
-				));
-				out_of_the_game.push(player);
-			}
-
-			event(Event::player(player, PlayerEvent::TurnEnd));
-		}
-
-		if out_of_the_game.len() == self.hands.len() {
-			event(Event::win(WinEvent::Dealer));
-			return;
-		}
-
-		// dealer
-		event(Event::dealer(DealerEvent::TurnStart));
-
-		while {
-			let score = self.dealer.score(&self.score);
-			score.1.unwrap_or(score.0) < 17
-		} {
-			event(Event::dealer(DealerEvent::Decision(Decision::Hit)));
-			let card = self.deck.draw().unwrap();
-			event(Event::dealer(DealerEvent::Draw(card)));
-			let hand = &mut self.dealer;
-			hand.add(card);
-			event(Event::dealer(DealerEvent::HandChange {
-				hand,
-				score: hand.score(&self.score),
-			}));
-		}
-
-		let dealer_bust = self.dealer.is_bust(&self.score);
-		if dealer_bust {
-			event(Event::dealer(DealerEvent::Bust {
-				score: self.dealer.score(&self.score).0,
-			}));
-
-			let players = (0..self.hands.len())
-				.filter(|player| !out_of_the_game.contains(player))
-				.collect();
-
-			event(Event::win(WinEvent::Players(players)));
-			event(Event::win(WinEvent::Lose(out_of_the_game)));
-			return;
-		} else {
-			event(Event::dealer(DealerEvent::Decision(Decision::Stand)));
-		}
-
-		event(Event::dealer(DealerEvent::TurnEnd));
-
-		// win
-		let scores: Vec<(usize, u8)> = (0..self.hands.len())
-			.filter(|player| !out_of_the_game.contains(player))
-			.map(|player| {
-				let score = self.hands[player].score(&self.score);
-				(player, score.1.unwrap_or(score.0))
-			})
-			.collect();
+				std::io::stdout().flush().unwrap();
 
-		let dealer_score = self.dealer.score(&self.score);
-		let dealer_score = dealer_score.1.unwrap_or(dealer_score.0);
+				if std::io::stdin().read_line(&mut buf).unwrap() == 0 {
+					end_session(&coach);
+				}
 
-		let winner = scores
-			.iter()
-			.filter(|(_, score)| score > &dealer_score)
-			.map(|(player, _)| *player)
-			.collect::<Vec<_>>();
-		let draws = scores
-			.iter()
-			.filter(|(_, score)| score == &dealer_score)
-			.map(|(player, _)| *player)
-			.collect::<Vec<_>>();
-		let losers = scores
-			.iter()
-			.filter(|(_, score)| score < &dealer_score)
-			.map(|(player, _)| *player)
-			.chain(out_of_the_game)
-			.collect::<Vec<_>>();
-
-		if winner.is_empty() {
-			if draws.is_empty() {
-				event(Event::win(WinEvent::Dealer));
-			} else {
-				event(Event::win(WinEvent::Push(draws)));
-			}
-		} else {
-			event(Event::win(WinEvent::Players(winner)));
-			if !draws.is_empty() {
-				event(Event::win(WinEvent::Push(draws)));
+				match buf.trim().parse() {
+					Ok(amount) => return amount,
+					Err(_) => buf.clear(),
+				}
 			}
-		}
-		if !losers.is_empty() {
-			event(Event::win(WinEvent::Lose(losers)));
-		}
-	}
-}
-
-fn main() {
-	let bjack = Blackjack::with_players(4, DECK_52);
-
-	let mut last_player = usize::MAX;
-
-	// TODO: cleanup
-	bjack.run(
-		|player, game| {
-			if player != last_player {
-				let score = game.score(player).unwrap();
+		},
+		|player, hand, game| {
+			let id = HandId::new(player, hand);
+			if last_hand != Some(id) {
 				println!(
-					">> Player {} hand {} (Score: {}{})",
-					player,
-					game.player(player)
-						.unwrap()
-						.cards()
-						.iter()
-						.map(|c| c.to_string())
-						.collect::<Vec<_>>()
-						.join(" "),
-					score.0,
-					score
-						.1
-						.map(|s| format!("/{}", s))
-						.unwrap_or_else(String::new)
+					">> Player {} hand {}",
+					id,
+					format_hand(
+						game.hand(player, hand).unwrap(),
+						game.score(player, hand).unwrap()
+					)
 				);
-				last_player = player;
+				last_hand = Some(id);
 			}
 
 			let mut buf = String::new();
 			loop {
 				println!(
 					"Options [H]it, [S]tand, [D]oubleDown, S[P]lit, \
-					 Su[R]render]"
+					 Su[R]render, [?]Hint"
 				);
-				print!(">> Player {} choose: ", player);
+				print!(">> Player {} choose: ", id);
 				std::io::stdout().flush().unwrap();
 
-				let _ = std::io::stdin().read_line(&mut buf).unwrap();
+				if std::io::stdin().read_line(&mut buf).unwrap() == 0 {
+					end_session(&coach);
+				}
 
-				buf.make_ascii_lowercase();
-				match buf.as_str().trim() {
-					"h" => return Decision::Hit,
-					"s" => return Decision::Stand,
-					"d" => return Decision::DoubleDown,
-					"p" => return Decision::Split,
-					"r" => return Decision::Surrender,
-					_ => buf.clear(),
-				};
+				if let Some(decision) = decision(&buf) {
+					return decision;
+				} else if buf.trim() == "?" {
+					if let Some(hint) = game.advise(&chart, player, hand) {
+						println!(">> Strategy: {}", hint);
+					}
+				}
+				buf.clear();
 			}
 		},
 		|event| {
+			if let Some(counter) = counter.borrow_mut().as_mut() {
+				counter.observe(&event);
+			}
+			if let Some(coach) = coach.borrow_mut().as_mut() {
+				coach.observe(&event);
+			}
+			if let Some(log) = &mut log {
+				log(&event);
+			}
+
 			match event {
-				Event::Player { player, event } => match event {
-					PlayerEvent::TurnStart => {
-						println!("--- >> Player {} << ---", player)
-					}
-					PlayerEvent::TurnEnd => {
-						println!("-------------------------")
-					}
-					PlayerEvent::ChooseDecision(_decision) => {}
-					PlayerEvent::ExecuteDecision(_decision) => {}
-					PlayerEvent::InvalidDecision {
-						invalid_decision: _invalid_decision,
-						reason,
-					} => println!(
-						"!! Player {} invalid decision: {}",
-						player, reason
-					),
-					PlayerEvent::Draw(card) => {
-						println!(">> Player {} drew {}", player, card)
-					}
-					PlayerEvent::InitialHand { hand, score } => println!(
-						">> Player {} initial hand {} (Score: {}{})",
-						player,
-						hand.cards()
-							.iter()
-							.map(|c| c.to_string())
-							.collect::<Vec<_>>()
-							.join(" "),
-						score.0,
-						score
-							.1
-							.map(|s| format!("/{}", s))
-							.unwrap_or_else(String::new)
-					),
-					PlayerEvent::HandChange { hand, score } => println!(
-						">> Player {} hand {} (Score: {}{})",
-						player,
-						hand.cards()
-							.iter()
-							.map(|c| c.to_string())
-							.collect::<Vec<_>>()
-							.join(" "),
-						score.0,
-						score
-							.1
-							.map(|s| format!("/{}", s))
-							.unwrap_or_else(String::new)
-					),
-					PlayerEvent::Bust { score } => {
-						println!(
-							">> Player {} went bust ({:?})",
-							player, score
-						)
-					}
-					PlayerEvent::Surrender => {
-						println!(">> Player {} surrendered", player)
-					}
-					PlayerEvent::Blackjack => {
-						println!(">> Player {} got a blackjack", player)
+				Event::Player { player, hand, event } => {
+					let id = HandId::new(player, hand);
+					match event {
+						PlayerEvent::TurnStart => {
+							println!("--- >> Player {} << ---", id)
+						}
+						PlayerEvent::TurnEnd => {
+							println!("-------------------------")
+						}
+						PlayerEvent::ChooseDecision(_decision) => {}
+						PlayerEvent::Mistake { .. } => {
+							if let Some(mistake) = coach
+								.borrow()
+								.as_ref()
+								.and_then(Coach::last_mistake)
+							{
+								println!("?? Coach: {}", mistake);
+							}
+						}
+						PlayerEvent::ExecuteDecision(_decision) => {}
+						PlayerEvent::InvalidDecision {
+							invalid_decision: _invalid_decision,
+							reason,
+						} => println!(
+							"!! Player {} invalid decision: {}",
+							id, reason
+						),
+						PlayerEvent::Bet(amount) => {
+							println!(">> Player {} bet {}", id, amount)
+						}
+						PlayerEvent::InvalidBet {
+							amount: _amount,
+							reason,
+						} => {
+							println!(
+								"!! Player {} invalid bet: {}",
+								id, reason
+							)
+						}
+						PlayerEvent::Draw(card) => {
+							println!(">> Player {} drew {}", id, card)
+						}
+						PlayerEvent::InitialHand { hand, score } => println!(
+							">> Player {} initial hand {}",
+							id,
+							format_hand(&hand, score)
+						),
+						PlayerEvent::HandChange { hand, score } => println!(
+							">> Player {} hand {}",
+							id,
+							format_hand(&hand, score)
+						),
+						PlayerEvent::Split { hand } => println!(
+							">> Player {} split into {}",
+							id,
+							HandId::new(player, hand)
+						),
+						PlayerEvent::Bust { score } => {
+							println!(
+								">> Player {} went bust ({:?})",
+								id, score
+							)
+						}
+						PlayerEvent::Surrender => {
+							println!(">> Player {} surrendered", id)
+						}
+						PlayerEvent::Blackjack => {
+							println!(">> Player {} got a blackjack", id)
+						}
+						PlayerEvent::Insurance(amount) => {
+							println!(">> Player {} insured {}", id, amount)
+						}
+						PlayerEvent::EvenMoney => {
+							println!(">> Player {} took even money", id)
+						}
+						PlayerEvent::InsurancePayout {
+							wager,
+							payout,
+							bankroll,
+						} => println!(
+							"$$ Player {} insurance (wager {}, payout {}, \
+							 bankroll {})",
+							id, wager, payout, bankroll
+						),
+						PlayerEvent::Payout {
+							outcome,
+							wager,
+							payout,
+							bankroll,
+						} => println!(
+							"$$ Player {} {} (wager {}, payout {}, bankroll {})",
+							id, outcome, wager, payout, bankroll
+						),
 					}
-				},
+				}
 				Event::Dealer(event) => match event {
 					DealerEvent::TurnStart => println!("--- >> Dealer << ---"),
 					DealerEvent::TurnEnd => {
@@ -851,52 +806,119 @@ fn main() {
 					DealerEvent::Draw(card) => {
 						println!(">> Dealer drew {}", card)
 					}
-					DealerEvent::InitialHand { hand, score } => println!(
-						">> Dealer initial hand {} (Score: {}{})",
-						hand.cards()
-							.iter()
-							.map(|c| c.to_string())
-							.collect::<Vec<_>>()
-							.join(" "),
-						score.0,
-						score
-							.1
-							.map(|s| format!("/{}", s))
-							.unwrap_or_else(String::new)
-					),
-					DealerEvent::HandChange { hand, score } => println!(
-						">> Dealer hand {} (Score: {}{})",
-						hand.cards()
-							.iter()
-							.map(|c| c.to_string())
-							.collect::<Vec<_>>()
-							.join(" "),
-						score.0,
-						score
-							.1
-							.map(|s| format!("/{}", s))
-							.unwrap_or_else(String::new)
-					),
+					DealerEvent::Upcard(card) => {
+						println!(">> Dealer shows {}", card)
+					}
+					DealerEvent::HoleCard(card) => {
+						println!(">> Dealer reveals {}", card)
+					}
+					DealerEvent::HandChange { hand, score } => {
+						println!(
+							">> Dealer hand {}",
+							format_hand(&hand, score)
+						)
+					}
 					DealerEvent::Bust { score } => {
 						println!(">> Dealer went bust ({:?})", score)
 					}
 					DealerEvent::Blackjack => {
 						println!(">> Dealer got a blackjack")
 					}
+					DealerEvent::OfferInsurance => {
+						println!(">> Dealer offers insurance")
+					}
+					DealerEvent::Peek => {
+						println!(">> Dealer checks for a blackjack")
+					}
+					DealerEvent::Shuffle { .. } => {
+						println!(">> Dealer shuffles the discards back in")
+					}
 				},
 				Event::Win(event) => match event {
 					WinEvent::Dealer => println!("$$ Dealer won"),
-					WinEvent::Players(players) => {
-						println!("$$ Players {:?} won", players)
+					WinEvent::Players(hands) => {
+						println!("$$ Hands [{}] won", format_hands(&hands))
 					}
-					WinEvent::Push(players) => {
-						println!("$$ Dealer and Players {:?} push", players)
+					WinEvent::Push(hands) => {
+						println!(
+							"$$ Dealer and hands [{}] push",
+							format_hands(&hands)
+						)
 					}
-					WinEvent::Lose(players) => {
-						println!("$$ Players {:?} lose", players)
+					WinEvent::Lose(hands) => {
+						println!("$$ Hands [{}] lose", format_hands(&hands))
 					}
 				},
 			};
 		},
+		|table| {
+			println!("=========================");
+			for seat in table.players() {
+				println!(
+					">> Player {} bankroll {}",
+					seat,
+					table.bankroll(seat).unwrap()
+				);
+			}
+			if let Some(counter) = counter.borrow_mut().as_mut() {
+				println!(
+					">> Running count {:+}, true count {:+.1}{}",
+					counter.running_count(),
+					counter.true_count(),
+					if counter.favorable() { ", raise the bet" } else { "" }
+				);
+				if table.deck().cut_card_reached() {
+					counter.shuffle();
+				}
+			}
+			if let Some(coach) = coach.borrow_mut().as_mut() {
+				if table.deck().cut_card_reached() {
+					coach.shuffle();
+				}
+			}
+			if table.deck().cut_card_reached() {
+				println!(">> Cut card reached, shuffling the shoe");
+			}
+
+			let mut buf = String::new();
+			loop {
+				print!(
+					">> [Enter] next round, [J]oin, [L]eave <seat>, [Q]uit: "
+				);
+				std::io::stdout().flush().unwrap();
+
+				if std::io::stdin().read_line(&mut buf).unwrap() == 0 {
+					println!();
+					return false;
+				}
+
+				buf.make_ascii_lowercase();
+				let mut args = buf.split_whitespace();
+				match (args.next(), args.next().map(str::parse::<usize>)) {
+					(None, _) => return true,
+					(Some("q"), None) => return false,
+					(Some("j"), None) => match table.join(100) {
+						Some(seat) => println!(">> Player {} joined", seat),
+						None => println!("!! No free seat"),
+					},
+					(Some("l"), Some(Ok(seat))) => match table.leave(seat) {
+						Ok(bankroll) => println!(
+							">> Player {} left with {}",
+							seat, bankroll
+						),
+						Err(error) => println!("!! {}", error),
+					},
+					_ => {}
+				}
+				buf.clear();
+			}
+		},
 	);
+
+	if let Err(error) = session {
+		println!("!! {}", error);
+	}
+	if let Some(coach) = coach.into_inner() {
+		println!(">> Coach: {}", coach);
+	}
 }
@@ -0,0 +1,258 @@
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::card::Card;
+use crate::event::{DealerEvent, Event, PlayerEvent};
+use crate::hand::Hand;
+use crate::score::BlackjackScore;
+
+// An event together with the table it happened at, which makes sense on its
+// own, like a line of a log. Events themselves carry no seats or totals, this
+// is their self-describing form. `cards` and `score` are those of the hand the
+// event is about once it happened, the dealer hand for dealer events and
+// nothing for the results of the round.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Record {
+	pub round: u64,
+	pub seat: Option<usize>,
+	// sub-hand of the seat, hands created by splits count up from 1
+	pub hand: Option<usize>,
+	pub cards: Vec<Card>,
+	pub score: Option<(u8, Option<u8>)>,
+	// the cards the dealer shows
+	pub dealer: Vec<Card>,
+	pub event: Event<'static>,
+}
+
+// Follows the events of a table and keeps the hands on it to turn the
+// events into records.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Recorder {
+	round: u64,
+	// whether the last round was settled and the next event starts a new one
+	settled: bool,
+	hands: Vec<Vec<Hand>>,
+	dealer: Hand,
+}
+
+impl Default for Recorder {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl Recorder {
+	pub fn new() -> Self {
+		Self {
+			round: 0,
+			settled: true,
+			hands: Vec::new(),
+			dealer: Hand::default(),
+		}
+	}
+
+	// The round of the last event, counting from 1.
+	pub const fn round(&self) -> u64 {
+		self.round
+	}
+
+	pub fn record(&mut self, event: &Event<'_>) -> Record {
+		let settlement = matches!(
+			event,
+			Event::Win(_)
+				| Event::Player { event: PlayerEvent::Payout { .. }, .. }
+		);
+		if self.settled && !settlement {
+			self.round += 1;
+			self.hands.clear();
+			self.dealer = Hand::default();
+		}
+		self.settled = settlement;
+
+		let (seat, hand, cards) = match event {
+			Event::Player { player, hand, event } => {
+				self.player(*player, *hand, event);
+				let cards = self.hands[*player][*hand].clone();
+				(Some(*player), Some(*hand), Some(cards))
+			}
+			Event::Dealer(event) => {
+				self.dealer(event);
+				(None, None, Some(self.dealer.clone()))
+			}
+			Event::Win(_) => (None, None, None),
+		};
+
+		Record {
+			round: self.round,
+			seat,
+			hand,
+			score: cards.as_ref().map(|cards| cards.score(&BlackjackScore)),
+			cards: cards
+				.map(|cards| cards.cards().to_vec())
+				.unwrap_or_default(),
+			dealer: self.dealer.cards().to_vec(),
+			event: event.clone().into_owned(),
+		}
+	}
+
+	fn player(&mut self, player: usize, hand: usize, event: &PlayerEvent<'_>) {
+		if self.hands.len() <= player {
+			self.hands.resize(player + 1, Vec::new());
+		}
+		let hands = &mut self.hands[player];
+		if hands.len() <= hand {
+			hands.resize(hand + 1, Hand::default());
+		}
+
+		match event {
+			PlayerEvent::InitialHand { hand: cards, .. }
+			| PlayerEvent::HandChange { hand: cards, .. } => {
+				hands[hand] = cards.clone().into_owned();
+			}
+			PlayerEvent::Draw(card) => hands[hand].add(*card),
+			// the second card goes to the new hand
+			PlayerEvent::Split { hand: split } => {
+				if let Some(new) = hands[hand].split() {
+					if hands.len() <= *split {
+						hands.resize(split + 1, Hand::default());
+					}
+					hands[*split] = new;
+				}
+			}
+			_ => {}
+		}
+	}
+
+	fn dealer(&mut self, event: &DealerEvent<'_>) {
+		match event {
+			DealerEvent::Upcard(card) => {
+				self.dealer = Hand::default();
+				self.dealer.add(*card);
+			}
+			DealerEvent::Draw(card) | DealerEvent::HoleCard(card) => {
+				self.dealer.add(*card)
+			}
+			DealerEvent::HandChange { hand, .. } => {
+				self.dealer = hand.clone().into_owned();
+			}
+			_ => {}
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use rand::rngs::StdRng;
+	use rand::SeedableRng;
+
+	use super::*;
+	use crate::deck::Stacked;
+	use crate::event::WinEvent;
+	use crate::game::Decision;
+	use crate::hand::HandId;
+	use crate::rules::Rules;
+	use crate::table::Table;
+
+	fn cards(cards: &str) -> Vec<Card> {
+		cards.split_whitespace().map(|card| card.parse().unwrap()).collect()
+	}
+
+	// Two rounds of a player on the second seat who stands on 17 and 19
+	// against a dealer who stands on 17.
+	fn records() -> Vec<Record> {
+		let mut table = Table::with_rng(
+			2,
+			Stacked::new("Th 7c 9s 8d Tc 9c 7s Ts"),
+			Rules::default(),
+			StdRng::seed_from_u64(0),
+		);
+		table.join_at(1, 100).unwrap();
+
+		let mut recorder = Recorder::new();
+		let mut records = Vec::new();
+		for _ in 0..2 {
+			table
+				.round(
+					|_, _, _| 10,
+					|_, _, _| Decision::Stand,
+					|event| records.push(recorder.record(&event)),
+				)
+				.unwrap();
+		}
+		assert_eq!(recorder.round(), 2);
+		records
+	}
+
+	#[test]
+	fn records_count_the_rounds() {
+		let records = records();
+		let first = records.iter().take_while(|record| record.round == 1);
+		assert_eq!(first.count(), records.len() / 2);
+		assert!(records[records.len() / 2..].iter().all(|r| r.round == 2));
+
+		// the payouts after the results still belong to the round
+		let last = records.last().unwrap();
+		assert!(matches!(
+			last.event,
+			Event::Player {
+				event: PlayerEvent::Payout { payout: 20, .. },
+				..
+			}
+		));
+		assert_eq!(last.round, 2);
+	}
+
+	#[test]
+	fn records_know_the_seat_and_the_dealer() {
+		let records = &records()[..14];
+		for record in records {
+			match &record.event {
+				Event::Player { .. } => {
+					assert_eq!((record.seat, record.hand), (Some(1), Some(0)))
+				}
+				_ => assert_eq!((record.seat, record.hand), (None, None)),
+			}
+		}
+
+		let initial = &records[1];
+		assert_eq!(initial.cards, cards("Th 7c"));
+		assert_eq!(initial.score, Some((17, None)));
+		assert!(initial.dealer.is_empty());
+
+		// the hole card is only shown once it is revealed
+		let turn = records
+			.iter()
+			.find(|record| {
+				record.event
+					== Event::player(
+						1,
+						0,
+						PlayerEvent::ExecuteDecision(Decision::Stand),
+					)
+			})
+			.unwrap();
+		assert_eq!(turn.dealer, cards("9s"));
+
+		let push = records
+			.iter()
+			.find(|record| matches!(record.event, Event::Win(_)))
+			.unwrap();
+		assert_eq!(
+			push.event,
+			Event::Win(WinEvent::Push(vec![HandId::new(1, 0)]))
+		);
+		assert!(push.cards.is_empty());
+		assert_eq!(push.score, None);
+		assert_eq!(push.dealer, cards("9s 8d"));
+	}
+
+	#[cfg(feature = "serde")]
+	#[test]
+	fn records_round_trip_as_json() {
+		for record in records() {
+			let json = serde_json::to_string(&record).unwrap();
+			assert_eq!(serde_json::from_str::<Record>(&json).unwrap(), record);
+		}
+	}
+}
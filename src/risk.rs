@@ -0,0 +1,392 @@
+use std::fmt;
+
+use rand::RngCore;
+
+use crate::simulation::{parallel, Interrupted, Simulation, Statistics};
+use crate::strategy::Strategy;
+
+// Plays sessions of `rounds` rounds where every player starts with
+// `bankroll` units and plays until the session ends or the bankroll is gone.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Risk {
+	pub simulation: Simulation,
+	// starting bankroll in units
+	pub bankroll: u64,
+	pub rounds: u64,
+	// number of points in the trajectories after the start
+	pub steps: u64,
+}
+
+impl Risk {
+	pub const fn new(simulation: Simulation, bankroll: u64) -> Self {
+		Self { simulation, bankroll, rounds: 10_000, steps: 20 }
+	}
+
+	// Rounds after which the bankrolls are recorded, starting with 0.
+	pub fn checkpoints(&self) -> Vec<u64> {
+		// sessions without rounds end where they start
+		if self.rounds == 0 {
+			return vec![0];
+		}
+		let steps = self.steps.clamp(1, self.rounds.max(1));
+		(0..=steps).map(|step| self.rounds * step / steps).collect()
+	}
+
+	// Each session is played with a fresh shoe and every seat of it is a
+	// player with a bankroll of their own. A session which can not be
+	// finished stops the run, its players are left out of the report.
+	pub fn run(
+		&self,
+		sessions: u64,
+		strategy: &dyn Strategy,
+		rng: &mut dyn RngCore,
+	) -> Result<RiskReport, Interrupted<RiskReport>> {
+		let simulation = &self.simulation;
+		let start = self.bankroll * simulation.bet;
+		let mut report = RiskReport::new(self);
+		let RiskReport { checkpoints, bankrolls, doubled, statistics, .. } =
+			&mut report;
+
+		for _ in 0..sessions {
+			let mut table = simulation.table(&mut *rng);
+			while table.join(start).is_some() {}
+
+			let mut doubling = vec![false; table.seats()];
+			let mut round = 0;
+			let mut next = 1;
+			let players = bankrolls[0].len();
+			bankrolls[0].extend((0..table.seats()).map(|_| start));
+
+			let played = simulation.play(
+				&mut table,
+				self.rounds,
+				strategy,
+				statistics,
+				|table| {
+					let bankroll = |seat| table.bankroll(seat).unwrap_or(0);
+					round += 1;
+					for (seat, doubling) in doubling.iter_mut().enumerate() {
+						*doubling |= bankroll(seat) >= 2 * start;
+					}
+					if checkpoints.get(next) == Some(&round) {
+						bankrolls[next]
+							.extend((0..table.seats()).map(bankroll));
+						next += 1;
+					}
+				},
+			);
+			if let Err(error) = played {
+				for bankrolls in bankrolls.iter_mut() {
+					bankrolls.truncate(players);
+				}
+				report.sort();
+				return Err(Interrupted { error, partial: Box::new(report) });
+			}
+
+			// everybody went broke before the session ended
+			for bankrolls in &mut bankrolls[next..] {
+				bankrolls.extend((0..table.seats()).map(|_| 0));
+			}
+			*doubled +=
+				doubling.iter().filter(|&&doubling| doubling).count() as u64;
+		}
+
+		report.sort();
+		Ok(report)
+	}
+
+	// Splits the sessions into chunks of `CHUNK` sessions and plays them on
+	// `threads` threads like `Simulation::run_parallel`.
+	pub fn run_parallel(
+		&self,
+		sessions: u64,
+		threads: usize,
+		strategy: &(dyn Strategy + Sync),
+		seed: u64,
+	) -> Result<RiskReport, Interrupted<RiskReport>> {
+		parallel(
+			sessions,
+			CHUNK,
+			threads,
+			seed,
+			RiskReport::new(self),
+			|share, rng| self.run(share, strategy, rng),
+			RiskReport::extend,
+		)
+		.map(|mut report| {
+			report.sort();
+			report
+		})
+		.map_err(|mut interrupted| {
+			interrupted.partial.sort();
+			interrupted
+		})
+	}
+}
+
+// sessions played with one generator by `Risk::run_parallel`
+const CHUNK: u64 = 10;
+
+// label, CSV column and share of the players
+const PERCENTILES: [(&str, &str, f64); 5] = [
+	("5%", "p5", 0.05),
+	("25%", "p25", 0.25),
+	("Median", "median", 0.5),
+	("75%", "p75", 0.75),
+	("95%", "p95", 0.95),
+];
+
+// Bankrolls of the players of a `Risk` run. The analytic estimates assume
+// the results of the rounds are normally distributed with the simulated
+// expected value and variance. Values are in units.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RiskReport {
+	bankroll: u64,
+	checkpoints: Vec<u64>,
+	// chips of every player at each checkpoint, sorted once the run is done
+	bankrolls: Vec<Vec<u64>>,
+	// players who had twice their starting bankroll at some point
+	doubled: u64,
+	statistics: Statistics,
+}
+
+impl RiskReport {
+	fn new(risk: &Risk) -> Self {
+		let checkpoints = risk.checkpoints();
+		Self {
+			bankroll: risk.bankroll,
+			bankrolls: vec![Vec::new(); checkpoints.len()],
+			checkpoints,
+			doubled: 0,
+			statistics: Statistics::new(risk.simulation.bet),
+		}
+	}
+
+	fn sort(&mut self) {
+		for bankrolls in &mut self.bankrolls {
+			bankrolls.sort_unstable();
+		}
+	}
+
+	// Adds the players of `other`, which has to be run with the same
+	// settings.
+	pub fn merge(&mut self, other: &Self) {
+		self.extend(other);
+		self.sort();
+	}
+
+	// Like `merge`, but leaves the bankrolls unsorted.
+	fn extend(&mut self, other: &Self) {
+		for (bankrolls, others) in
+			self.bankrolls.iter_mut().zip(&other.bankrolls)
+		{
+			bankrolls.extend(others);
+		}
+		self.doubled += other.doubled;
+		self.statistics.merge(&other.statistics);
+	}
+
+	pub const fn statistics(&self) -> &Statistics {
+		&self.statistics
+	}
+
+	// The starting bankroll in units.
+	pub const fn bankroll(&self) -> u64 {
+		self.bankroll
+	}
+
+	pub fn checkpoints(&self) -> &[u64] {
+		&self.checkpoints
+	}
+
+	pub fn players(&self) -> u64 {
+		self.bankrolls.first().map_or(0, Vec::len) as u64
+	}
+
+	fn rate(&self, count: u64) -> f64 {
+		if self.players() == 0 {
+			return 0.0;
+		}
+		count as f64 / self.players() as f64
+	}
+
+	// Share of the players who went broke by the checkpoint `step`.
+	pub fn ruined(&self, step: usize) -> f64 {
+		let bankrolls = &self.bankrolls[step];
+		self.rate(bankrolls.partition_point(|&bankroll| bankroll == 0) as u64)
+	}
+
+	// Share of the players who went broke before the sessions ended.
+	pub fn risk_of_ruin(&self) -> f64 {
+		self.ruined(self.checkpoints.len() - 1)
+	}
+
+	// Share of the players who doubled their bankroll at some point.
+	pub fn doubling(&self) -> f64 {
+		self.rate(self.doubled)
+	}
+
+	// The bankroll at the checkpoint `step` which a share `p` of the
+	// players did not exceed.
+	pub fn percentile(&self, step: usize, p: f64) -> f64 {
+		let bankrolls = &self.bankrolls[step];
+		if bankrolls.is_empty() {
+			return 0.0;
+		}
+		let rank = (p * bankrolls.len() as f64).ceil() as usize;
+		let chips = bankrolls[rank.clamp(1, bankrolls.len()) - 1];
+		chips as f64 / self.statistics.bet() as f64
+	}
+
+	pub fn median(&self, step: usize) -> f64 {
+		self.percentile(step, 0.5)
+	}
+
+	// Risk of ever going broke when playing forever.
+	pub fn ruin_estimate(&self) -> f64 {
+		let (ev, variance) =
+			(self.statistics.ev(), self.statistics.variance());
+		if ev <= 0.0 || variance == 0.0 {
+			return 1.0;
+		}
+		(-2.0 * ev * self.bankroll as f64 / variance).exp()
+	}
+
+	// Rounds until the expected value equals one standard deviation.
+	pub fn n0(&self) -> f64 {
+		let ev = self.statistics.ev();
+		if ev <= 0.0 {
+			return f64::INFINITY;
+		}
+		self.statistics.variance() / (ev * ev)
+	}
+
+	// The unit which grows the starting bankroll the fastest with the same
+	// bet ramp, in current units. Nothing without an advantage.
+	pub fn kelly_unit(&self) -> f64 {
+		let (ev, variance) =
+			(self.statistics.ev(), self.statistics.variance());
+		if ev <= 0.0 || variance == 0.0 {
+			return 0.0;
+		}
+		self.bankroll as f64 * ev / variance
+	}
+
+	// The trajectories for plotting, one row per checkpoint.
+	pub fn to_csv(&self) -> String {
+		let mut csv = String::from("round");
+		for (_, column, _) in &PERCENTILES {
+			csv.push(',');
+			csv.push_str(column);
+		}
+		csv.push_str(",ruined\n");
+
+		for (step, round) in self.checkpoints.iter().enumerate() {
+			csv.push_str(&round.to_string());
+			for (_, _, p) in &PERCENTILES {
+				csv.push_str(&format!(",{}", self.percentile(step, *p)));
+			}
+			csv.push_str(&format!(",{}\n", self.ruined(step)));
+		}
+
+		csv
+	}
+}
+
+impl fmt::Display for RiskReport {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let statistics = &self.statistics;
+		let rounds = self.checkpoints.last().copied().unwrap_or(0);
+
+		writeln!(f, "Players       {:>12}", self.players())?;
+		writeln!(f, "Bankroll      {:>12}", self.bankroll)?;
+		writeln!(f, "Rounds        {:>12}", rounds)?;
+		writeln!(f, "EV per round  {:>+11.4}%", statistics.ev() * 100.0)?;
+		writeln!(f, "Std. dev.     {:>12.4}", statistics.std_dev())?;
+		writeln!(f, "Risk of ruin  {:>11.3}%", self.risk_of_ruin() * 100.0)?;
+		writeln!(f, "  unlimited   {:>11.3}%", self.ruin_estimate() * 100.0)?;
+		writeln!(f, "Doubling      {:>11.3}%", self.doubling() * 100.0)?;
+		writeln!(f, "N0            {:>12.0}", self.n0())?;
+		writeln!(f, "Kelly unit    {:>12.4}", self.kelly_unit())?;
+		writeln!(f)?;
+
+		write!(f, "{:>10}", "Round")?;
+		for (label, ..) in &PERCENTILES {
+			write!(f, " {:>10}", label)?;
+		}
+		write!(f, " {:>8}", "Ruined")?;
+		for (step, round) in self.checkpoints.iter().enumerate() {
+			write!(f, "\n{:>10}", round)?;
+			for (_, _, p) in &PERCENTILES {
+				write!(f, " {:>10.1}", self.percentile(step, *p))?;
+			}
+			write!(f, " {:>7.2}%", self.ruined(step) * 100.0)?;
+		}
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::error::BlackjackError;
+	use crate::rules::Rules;
+	use crate::strategy::BasicStrategy;
+
+	fn risk(decks: usize) -> Risk {
+		let simulation = Simulation {
+			players: 3,
+			..Simulation::new(Rules::default(), decks)
+		};
+		Risk { rounds: 2000, ..Risk::new(simulation, 200) }
+	}
+
+	#[test]
+	fn a_single_deck_lasts_the_sessions() {
+		let risk = risk(1);
+		let chart = BasicStrategy::new(1).chart(&risk.simulation.rules);
+		let report = risk.run_parallel(12, 4, &chart, 21).unwrap();
+		assert_eq!(report.players(), 36);
+		assert_eq!(report.checkpoints().last(), Some(&2000));
+		assert!(report.statistics().rounds() > 0);
+	}
+
+	#[test]
+	fn interrupted_runs_keep_the_finished_sessions() {
+		let risk = risk(0);
+		let chart = BasicStrategy::new(1).chart(&risk.simulation.rules);
+		let interrupted = risk.run_parallel(12, 4, &chart, 21).unwrap_err();
+		assert_eq!(interrupted.error, BlackjackError::DeckExhausted);
+		assert_eq!(interrupted.partial.players(), 0);
+		assert_eq!(interrupted.partial.bankroll(), 200);
+	}
+
+	#[test]
+	fn sessions_without_rounds_ruin_nobody() {
+		let risk = Risk { rounds: 0, ..risk(6) };
+		let chart = BasicStrategy::new(6).chart(&risk.simulation.rules);
+		let report = risk.run_parallel(4, 2, &chart, 1).unwrap();
+		assert_eq!(report.checkpoints(), &[0]);
+		assert_eq!(report.players(), 12);
+		assert_eq!(report.risk_of_ruin(), 0.0);
+		assert_eq!(report.median(0), 200.0);
+	}
+
+	#[test]
+	fn small_bankrolls_go_broke_in_a_losing_game() {
+		let rules = "h17,6:5,ns,ndas".parse::<Rules>().unwrap();
+		let risk =
+			Risk { rounds: 1000, ..Risk::new(Simulation::new(rules, 6), 5) };
+		let chart = BasicStrategy::new(6).chart(&rules);
+		let report = risk.run_parallel(20, 4, &chart, 5).unwrap();
+
+		assert!(report.statistics().ev() < 0.0);
+		assert!(report.risk_of_ruin() > 0.5, "{}", report.risk_of_ruin());
+		assert_eq!(report.ruined(0), 0.0);
+		assert!(report.ruined(10) <= report.risk_of_ruin());
+		assert_eq!(report.ruin_estimate(), 1.0);
+		assert_eq!(report.kelly_unit(), 0.0);
+		assert_eq!(report.n0(), f64::INFINITY);
+	}
+}
@@ -0,0 +1,200 @@
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum DoubleDownRule {
+	// Double down on any first two cards.
+	Any,
+	// Double down only on a hard 9, 10 or 11.
+	NineToEleven,
+	// Double down only on a hard 10 or 11.
+	TenToEleven,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SurrenderRule {
+	None,
+	// Surrender after the dealer checked for a blackjack.
+	Late,
+	// Surrender before the dealer checks for a blackjack.
+	Early,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum HoleCardRule {
+	// US style: the dealer takes a hole card and peeks for a blackjack when
+	// showing an ace or a ten.
+	Peek,
+	// European style: the dealer's second card is drawn after all players
+	// acted. On a dealer blackjack players either lose only their original
+	// bets or everything they wagered. As the dealer can not check for a
+	// blackjack, any surrender is effectively an early surrender.
+	NoHoleCard { original_bets_only: bool },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Payout {
+	pub win: u32,
+	pub bet: u32,
+}
+
+impl Payout {
+	pub const EVEN: Self = Self::new(1, 1);
+	pub const SIX_TO_FIVE: Self = Self::new(6, 5);
+	pub const THREE_TO_TWO: Self = Self::new(3, 2);
+
+	pub const fn new(win: u32, bet: u32) -> Self {
+		Self { win, bet }
+	}
+}
+
+impl fmt::Display for Payout {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}:{}", self.win, self.bet)
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Rules {
+	pub dealer_hits_soft_17: bool,
+	pub double_down: DoubleDownRule,
+	pub double_after_split: bool,
+	// Maximum number of hands a player can hold by (re-)splitting.
+	pub max_split_hands: usize,
+	pub resplit_aces: bool,
+	pub hit_split_aces: bool,
+	pub surrender: SurrenderRule,
+	pub hole_card: HoleCardRule,
+	pub blackjack_payout: Payout,
+}
+
+impl Rules {
+	pub const fn new() -> Self {
+		Self {
+			dealer_hits_soft_17: false,
+			double_down: DoubleDownRule::Any,
+			double_after_split: true,
+			max_split_hands: 4,
+			resplit_aces: false,
+			hit_split_aces: false,
+			surrender: SurrenderRule::Late,
+			hole_card: HoleCardRule::Peek,
+			blackjack_payout: Payout::THREE_TO_TWO,
+		}
+	}
+}
+
+impl Default for Rules {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+// The rules in the short notation read by `str::parse`.
+impl fmt::Display for Rules {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"{},{},{},sp{},{},{},{},{},{}",
+			if self.dealer_hits_soft_17 { "h17" } else { "s17" },
+			match self.double_down {
+				DoubleDownRule::Any => "da2",
+				DoubleDownRule::NineToEleven => "d9",
+				DoubleDownRule::TenToEleven => "d10",
+			},
+			if self.double_after_split { "das" } else { "ndas" },
+			self.max_split_hands,
+			if self.resplit_aces { "rsa" } else { "nrsa" },
+			if self.hit_split_aces { "hsa" } else { "nhsa" },
+			match self.surrender {
+				SurrenderRule::None => "ns",
+				SurrenderRule::Late => "ls",
+				SurrenderRule::Early => "es",
+			},
+			match self.hole_card {
+				HoleCardRule::Peek => "peek",
+				HoleCardRule::NoHoleCard { original_bets_only: false } => {
+					"enhc"
+				}
+				HoleCardRule::NoHoleCard { original_bets_only: true } => "obo",
+			},
+			self.blackjack_payout
+		)
+	}
+}
+
+// Comma separated changes to the default rules, e.g. `h17,ndas,6:5`.
+impl FromStr for Rules {
+	type Err = ParseRulesError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let mut rules = Self::default();
+
+		for token in s.split(',').map(str::trim).filter(|t| !t.is_empty()) {
+			let invalid = || ParseRulesError(token.to_string());
+
+			match token.to_ascii_lowercase().as_str() {
+				"s17" => rules.dealer_hits_soft_17 = false,
+				"h17" => rules.dealer_hits_soft_17 = true,
+				"da2" => rules.double_down = DoubleDownRule::Any,
+				"d9" => rules.double_down = DoubleDownRule::NineToEleven,
+				"d10" => rules.double_down = DoubleDownRule::TenToEleven,
+				"das" => rules.double_after_split = true,
+				"ndas" => rules.double_after_split = false,
+				"rsa" => rules.resplit_aces = true,
+				"nrsa" => rules.resplit_aces = false,
+				"hsa" => rules.hit_split_aces = true,
+				"nhsa" => rules.hit_split_aces = false,
+				"ns" => rules.surrender = SurrenderRule::None,
+				"ls" => rules.surrender = SurrenderRule::Late,
+				"es" => rules.surrender = SurrenderRule::Early,
+				"peek" => rules.hole_card = HoleCardRule::Peek,
+				"enhc" => {
+					rules.hole_card =
+						HoleCardRule::NoHoleCard { original_bets_only: false }
+				}
+				"obo" => {
+					rules.hole_card =
+						HoleCardRule::NoHoleCard { original_bets_only: true }
+				}
+				split if split.starts_with("sp") => {
+					rules.max_split_hands = split[2..]
+						.parse()
+						.ok()
+						.filter(|&hands| hands >= 1)
+						.ok_or_else(invalid)?
+				}
+				payout => {
+					let (win, bet) =
+						payout.split_once(':').ok_or_else(invalid)?;
+					rules.blackjack_payout = Payout::new(
+						win.parse().map_err(|_| invalid())?,
+						bet.parse()
+							.ok()
+							.filter(|&bet| bet > 0)
+							.ok_or_else(invalid)?,
+					)
+				}
+			}
+		}
+
+		Ok(rules)
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ParseRulesError(pub String);
+
+impl fmt::Display for ParseRulesError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"Invalid rule `{}`, expected one of s17, h17, da2, d9, d10, das, \
+			 ndas, spN, rsa, nrsa, hsa, nhsa, ns, ls, es, peek, enhc, obo or a \
+			 payout like 3:2",
+			self.0
+		)
+	}
+}
+
+impl std::error::Error for ParseRulesError {}
@@ -0,0 +1,41 @@
+use crate::card::Rank;
+use crate::hand::Hand;
+
+pub trait Score {
+	type Output;
+
+	fn score(&self, hand: &Hand) -> Self::Output;
+	fn is_bust(&self, hand: &Hand) -> bool;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BlackjackScore;
+
+impl BlackjackScore {
+	pub const fn value(rank: &Rank) -> u8 {
+		let value = *rank as u8 + 1;
+		if value > 10 {
+			10
+		} else {
+			value
+		}
+	}
+}
+
+impl Score for BlackjackScore {
+	type Output = (u8, Option<u8>);
+
+	fn score(&self, hand: &Hand) -> Self::Output {
+		let score = hand.cards().iter().map(|c| Self::value(c.rank())).sum();
+
+		if score <= 11 && hand.cards().iter().any(|c| c.rank() == &Rank::Ace) {
+			(score, Some(score + 10))
+		} else {
+			(score, None)
+		}
+	}
+
+	fn is_bust(&self, hand: &Hand) -> bool {
+		self.score(hand).0 > 21
+	}
+}
@@ -0,0 +1,596 @@
+use std::cell::RefCell;
+use std::fmt;
+
+use rand::rngs::StdRng;
+use rand::{Rng, RngCore, SeedableRng};
+
+use crate::counting::{Counter, CountingPlayer};
+use crate::deck::{Deck, Shoe};
+use crate::error::BlackjackError;
+use crate::event::{Event, Outcome, PlayerEvent, WinEvent};
+use crate::game::{Decision, Wager};
+use crate::rules::Rules;
+use crate::strategy::Strategy;
+use crate::table::Table;
+
+// rounds played with one generator by `Simulation::run_parallel`
+const CHUNK: u64 = 1000;
+
+// Plays rounds at a table where every seat is taken by an automated player
+// who bets `bet` chips, declines insurance and decides by a strategy. With
+// `counting` the players count the shoe and bet in units of `bet` instead.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Simulation {
+	pub rules: Rules,
+	pub decks: usize,
+	pub penetration: f64,
+	pub players: usize,
+	// Big enough for every blackjack payout to be a whole number of chips.
+	pub bet: u64,
+	pub counting: Option<CountingPlayer>,
+}
+
+impl Simulation {
+	pub const fn new(rules: Rules, decks: usize) -> Self {
+		Self {
+			rules,
+			decks,
+			penetration: 0.75,
+			players: 1,
+			bet: 10,
+			counting: None,
+		}
+	}
+
+	pub fn run(
+		&self,
+		rounds: u64,
+		strategy: &dyn Strategy,
+		rng: &mut dyn RngCore,
+	) -> Result<Statistics, Interrupted<Statistics>> {
+		let mut table = self.table(rng);
+		// the bankroll only has to outlast any number of rounds
+		while table.join(u64::MAX / 2).is_some() {}
+
+		let mut statistics = Statistics::new(self.bet);
+		match self.play(&mut table, rounds, strategy, &mut statistics, |_| {})
+		{
+			Ok(()) => Ok(statistics),
+			Err(error) => {
+				Err(Interrupted { error, partial: Box::new(statistics) })
+			}
+		}
+	}
+
+	// Splits the rounds into chunks of `CHUNK` rounds, each playing its own
+	// shoe with a seed drawn from `seed`, and plays them on `threads`
+	// threads. The same seed always gives the same statistics, however many
+	// threads play them.
+	pub fn run_parallel(
+		&self,
+		rounds: u64,
+		threads: usize,
+		strategy: &(dyn Strategy + Sync),
+		seed: u64,
+	) -> Result<Statistics, Interrupted<Statistics>> {
+		parallel(
+			rounds,
+			CHUNK,
+			threads,
+			seed,
+			Statistics::new(self.bet),
+			|share, rng| self.run(share, strategy, rng),
+			Statistics::merge,
+		)
+	}
+
+	pub(crate) fn table<R: RngCore>(&self, rng: R) -> Table<Shoe, R> {
+		Table::with_rng(
+			self.players,
+			Shoe::new(self.decks, self.penetration),
+			self.rules,
+			rng,
+		)
+	}
+
+	// Plays up to `rounds` rounds with the players seated at `table` and
+	// calls `round` after each of them. Players never bet more than their
+	// bankroll and leave the table when it is gone. A round which can not be
+	// finished stops the play, `statistics` keep the rounds before it.
+	pub(crate) fn play<R, F>(
+		&self,
+		table: &mut Table<Shoe, R>,
+		rounds: u64,
+		strategy: &dyn Strategy,
+		statistics: &mut Statistics,
+		mut round: F,
+	) -> Result<(), BlackjackError>
+	where
+		R: RngCore,
+		F: FnMut(&Table<Shoe, R>),
+	{
+		let mut results = vec![0; table.seats()];
+		let mut bets = vec![0; table.seats()];
+		// all players keep the same count
+		let counter = RefCell::new(
+			self.counting
+				.as_ref()
+				.map(|counting| Counter::new(counting.system, self.decks)),
+		);
+
+		for _ in 0..rounds {
+			if table.is_empty() {
+				break;
+			}
+			if table.deck().needs_shuffle() {
+				if let Some(counter) = counter.borrow_mut().as_mut() {
+					counter.shuffle();
+				}
+			}
+
+			table.round(
+				|player, wager, game| {
+					let amount =
+						match (wager, &self.counting, &*counter.borrow()) {
+							(Wager::Bet, Some(counting), Some(counter)) => {
+								counting.ramp.units(counter.true_count())
+									* self.bet
+							}
+							(Wager::Insurance { max }, _, Some(counter))
+								if counter.insurance() =>
+							{
+								max
+							}
+							(Wager::Bet, ..) => self.bet,
+							(Wager::Insurance { .. }, ..) => 0,
+						};
+					// a player short of money bets what is left, insurance
+					// is already limited by the bankroll
+					match wager {
+						Wager::Bet => {
+							amount.min(game.bankroll(player).unwrap_or(0))
+						}
+						Wager::Insurance { .. } => amount,
+					}
+				},
+				|player, hand, game| match (
+					&self.counting,
+					&*counter.borrow(),
+					game.hand(player, hand),
+					game.upcard(),
+				) {
+					(
+						Some(counting),
+						Some(counter),
+						Some(current),
+						Some(upcard),
+					) => counting.decide(
+						strategy,
+						current,
+						upcard,
+						game.rules(),
+						counter.true_count(),
+						&|decision| game.is_legal(player, hand, decision),
+					),
+					_ => game
+						.advise(strategy, player, hand)
+						.unwrap_or(Decision::Stand),
+				},
+				|event| {
+					if let Some(counter) = counter.borrow_mut().as_mut() {
+						counter.observe(&event);
+					}
+					statistics.observe(&event, &mut results, &mut bets);
+				},
+			)?;
+
+			// players who sat the round out bet nothing
+			for (result, bet) in results.iter_mut().zip(&mut bets) {
+				if *bet > 0 {
+					statistics.record(*result, *bet);
+				}
+				*result = 0;
+				*bet = 0;
+			}
+
+			round(table);
+		}
+
+		Ok(())
+	}
+}
+
+// A run which stopped at a round that could not be finished, with the
+// results of the rounds played before it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Interrupted<T> {
+	pub error: BlackjackError,
+	pub partial: Box<T>,
+}
+
+impl<T> fmt::Display for Interrupted<T> {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "Stopped early: {}", self.error)
+	}
+}
+
+impl<T: fmt::Debug> std::error::Error for Interrupted<T> {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		Some(&self.error)
+	}
+}
+
+// Splits `count` runs into chunks of `chunk` runs, each with its own
+// generator seeded from `seed`, and spreads the chunks over `threads`
+// threads. The results are merged into `total` in the order of the chunks,
+// so the number of threads does not change them. The results of interrupted
+// chunks are merged as well.
+pub(crate) fn parallel<T, F, M>(
+	count: u64,
+	chunk: u64,
+	threads: usize,
+	seed: u64,
+	mut total: T,
+	run: F,
+	merge: M,
+) -> Result<T, Interrupted<T>>
+where
+	T: Send,
+	F: Fn(u64, &mut StdRng) -> Result<T, Interrupted<T>> + Sync,
+	M: Fn(&mut T, &T),
+{
+	let chunk = chunk.max(1);
+	let mut master = StdRng::seed_from_u64(seed);
+	let seeds =
+		(0..count.div_ceil(chunk)).map(|_| master.gen()).collect::<Vec<u64>>();
+	let threads = threads.clamp(1, seeds.len().max(1));
+
+	let mut results = std::thread::scope(|scope| {
+		let handles = (0..threads)
+			.map(|thread| {
+				let (run, seeds) = (&run, &seeds);
+				// every thread takes every `threads`th chunk, the last one
+				// has the remainder
+				scope.spawn(move || {
+					(thread..seeds.len())
+						.step_by(threads)
+						.map(|index| {
+							let share =
+								chunk.min(count - index as u64 * chunk);
+							let mut rng = StdRng::seed_from_u64(seeds[index]);
+							(index, run(share, &mut rng))
+						})
+						.collect::<Vec<_>>()
+				})
+			})
+			.collect::<Vec<_>>();
+
+		handles
+			.into_iter()
+			.flat_map(|handle| {
+				handle
+					.join()
+					.unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+			})
+			.collect::<Vec<_>>()
+	});
+	results.sort_unstable_by_key(|&(index, _)| index);
+
+	let mut error = None;
+	for (_, result) in results {
+		match result {
+			Ok(partial) => merge(&mut total, &partial),
+			Err(interrupted) => {
+				error.get_or_insert(interrupted.error);
+				merge(&mut total, &interrupted.partial);
+			}
+		}
+	}
+
+	match error {
+		Some(error) => Err(Interrupted { error, partial: Box::new(total) }),
+		None => Ok(total),
+	}
+}
+
+// Results of simulated rounds. Money is counted in whole chips, so the
+// statistics of separate runs add up exactly. Values per round are in units
+// of `bet`.
+#[derive(
+	Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+pub struct Statistics {
+	bet: u64,
+	// initial hands played, one per player and round
+	rounds: u64,
+	// sum of the initial bets and the smallest and largest of them
+	wagered: u64,
+	min_bet: u64,
+	max_bet: u64,
+	// net chips won by all rounds and the sum of their squares
+	total: i64,
+	squares: u64,
+	// settled hands, including split hands
+	hands: u64,
+	wins: u64,
+	pushes: u64,
+	losses: u64,
+	blackjacks: u64,
+	busts: u64,
+	surrenders: u64,
+}
+
+impl Statistics {
+	pub const fn new(bet: u64) -> Self {
+		Self {
+			bet,
+			rounds: 0,
+			wagered: 0,
+			min_bet: 0,
+			max_bet: 0,
+			total: 0,
+			squares: 0,
+			hands: 0,
+			wins: 0,
+			pushes: 0,
+			losses: 0,
+			blackjacks: 0,
+			busts: 0,
+			surrenders: 0,
+		}
+	}
+
+	// Counts the outcomes of a round, adds the money won by each player to
+	// `results` and keeps their initial bets in `bets`.
+	fn observe(
+		&mut self,
+		event: &Event<'_>,
+		results: &mut [i64],
+		bets: &mut [u64],
+	) {
+		match event {
+			Event::Win(WinEvent::Players(hands)) => {
+				self.wins += hands.len() as u64
+			}
+			Event::Win(WinEvent::Push(hands)) => {
+				self.pushes += hands.len() as u64
+			}
+			Event::Win(WinEvent::Lose(hands)) => {
+				self.losses += hands.len() as u64
+			}
+			Event::Win(WinEvent::Dealer) => {}
+			Event::Player { player, event, .. } => match event {
+				PlayerEvent::Blackjack => self.blackjacks += 1,
+				PlayerEvent::Bust { .. } => self.busts += 1,
+				PlayerEvent::Bet(amount) if bets[*player] == 0 => {
+					bets[*player] = *amount
+				}
+				PlayerEvent::InsurancePayout { wager, payout, .. } => {
+					results[*player] += *payout as i64 - *wager as i64
+				}
+				PlayerEvent::Payout { outcome, wager, payout, .. } => {
+					self.hands += 1;
+					if *outcome == Outcome::Surrender {
+						self.surrenders += 1;
+					}
+					results[*player] += *payout as i64 - *wager as i64;
+				}
+				_ => {}
+			},
+			Event::Dealer(_) => {}
+		}
+	}
+
+	fn record(&mut self, result: i64, bet: u64) {
+		self.min_bet =
+			if self.rounds == 0 { bet } else { self.min_bet.min(bet) };
+		self.max_bet = self.max_bet.max(bet);
+		self.rounds += 1;
+		self.wagered += bet;
+		self.total += result;
+		self.squares += result.unsigned_abs() * result.unsigned_abs();
+	}
+
+	// Adds the rounds of `other`, which has to be played with the same bet.
+	pub fn merge(&mut self, other: &Self) {
+		if other.rounds > 0 {
+			self.min_bet = if self.rounds == 0 {
+				other.min_bet
+			} else {
+				self.min_bet.min(other.min_bet)
+			};
+		}
+		self.max_bet = self.max_bet.max(other.max_bet);
+		self.rounds += other.rounds;
+		self.wagered += other.wagered;
+		self.total += other.total;
+		self.squares += other.squares;
+		self.hands += other.hands;
+		self.wins += other.wins;
+		self.pushes += other.pushes;
+		self.losses += other.losses;
+		self.blackjacks += other.blackjacks;
+		self.busts += other.busts;
+		self.surrenders += other.surrenders;
+	}
+
+	pub const fn bet(&self) -> u64 {
+		self.bet
+	}
+
+	pub const fn rounds(&self) -> u64 {
+		self.rounds
+	}
+
+	pub const fn hands(&self) -> u64 {
+		self.hands
+	}
+
+	// Net chips won over all rounds.
+	pub const fn total(&self) -> i64 {
+		self.total
+	}
+
+	// Net chips won per chip of initial bets.
+	pub fn advantage(&self) -> f64 {
+		if self.wagered == 0 {
+			return 0.0;
+		}
+		self.total as f64 / self.wagered as f64
+	}
+
+	// The average initial bet in units.
+	pub fn average_bet(&self) -> f64 {
+		if self.rounds == 0 {
+			return 0.0;
+		}
+		self.wagered as f64 / self.rounds as f64 / self.bet as f64
+	}
+
+	// The smallest and largest initial bet in units.
+	pub fn spread(&self) -> (f64, f64) {
+		let bet = self.bet as f64;
+		(self.min_bet as f64 / bet, self.max_bet as f64 / bet)
+	}
+
+	// Expected value and standard deviation of `rounds` rounds in units,
+	// like an hour at the table.
+	pub fn over(&self, rounds: f64) -> (f64, f64) {
+		(self.ev() * rounds, self.std_dev() * rounds.sqrt())
+	}
+
+	// Expected value of a round in units.
+	pub fn ev(&self) -> f64 {
+		if self.rounds == 0 {
+			return 0.0;
+		}
+		self.total as f64 / self.rounds as f64 / self.bet as f64
+	}
+
+	pub fn variance(&self) -> f64 {
+		if self.rounds < 2 {
+			return 0.0;
+		}
+		let n = self.rounds as f64;
+		let bet = self.bet as f64;
+		let mean = self.total as f64 / n;
+		(self.squares as f64 - n * mean * mean) / (n - 1.0) / (bet * bet)
+	}
+
+	pub fn std_dev(&self) -> f64 {
+		self.variance().sqrt()
+	}
+
+	// Standard error of the expected value.
+	pub fn std_error(&self) -> f64 {
+		if self.rounds == 0 {
+			return 0.0;
+		}
+		self.std_dev() / (self.rounds as f64).sqrt()
+	}
+
+	// Confidence interval of the expected value for a z-score, 1.96 for 95%.
+	pub fn confidence_interval(&self, z: f64) -> (f64, f64) {
+		let margin = z * self.std_error();
+		(self.ev() - margin, self.ev() + margin)
+	}
+
+	fn frequency(&self, count: u64) -> f64 {
+		if self.hands == 0 {
+			return 0.0;
+		}
+		count as f64 / self.hands as f64
+	}
+
+	// Frequencies per settled hand.
+	pub fn win_rate(&self) -> f64 {
+		self.frequency(self.wins)
+	}
+
+	pub fn push_rate(&self) -> f64 {
+		self.frequency(self.pushes)
+	}
+
+	// Surrendered hands count as lost.
+	pub fn loss_rate(&self) -> f64 {
+		self.frequency(self.losses)
+	}
+
+	pub fn blackjack_rate(&self) -> f64 {
+		self.frequency(self.blackjacks)
+	}
+
+	pub fn bust_rate(&self) -> f64 {
+		self.frequency(self.busts)
+	}
+
+	pub fn surrender_rate(&self) -> f64 {
+		self.frequency(self.surrenders)
+	}
+}
+
+impl fmt::Display for Statistics {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let (low, high) = self.confidence_interval(1.96);
+
+		writeln!(f, "Rounds        {:>12}", self.rounds)?;
+		writeln!(f, "Hands         {:>12}", self.hands)?;
+		writeln!(f, "EV per round  {:>+11.4}%", self.ev() * 100.0)?;
+		writeln!(
+			f,
+			"95% interval  {:>+11.4}% to {:+.4}%",
+			low * 100.0,
+			high * 100.0
+		)?;
+		writeln!(f, "Std. dev.     {:>12.4}", self.std_dev())?;
+		writeln!(f, "Variance      {:>12.4}", self.variance())?;
+		writeln!(f, "Advantage     {:>+11.4}%", self.advantage() * 100.0)?;
+		writeln!(f, "Average bet   {:>12.4}", self.average_bet())?;
+		let (min, max) = self.spread();
+		writeln!(f, "Bet spread    {:>12}", format!("{}-{}", min, max))?;
+		writeln!(f, "Wins          {:>11.3}%", self.win_rate() * 100.0)?;
+		writeln!(f, "Pushes        {:>11.3}%", self.push_rate() * 100.0)?;
+		writeln!(f, "Losses        {:>11.3}%", self.loss_rate() * 100.0)?;
+		writeln!(f, "Blackjacks    {:>11.3}%", self.blackjack_rate() * 100.0)?;
+		writeln!(f, "Busts         {:>11.3}%", self.bust_rate() * 100.0)?;
+		write!(f, "Surrenders    {:>11.3}%", self.surrender_rate() * 100.0)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::counting::{BetRamp, CountingSystem};
+	use crate::strategy::BasicStrategy;
+
+	fn play(simulation: &Simulation, threads: usize, seed: u64) -> Statistics {
+		let chart =
+			BasicStrategy::new(simulation.decks).chart(&simulation.rules);
+		simulation.run_parallel(4500, threads, &chart, seed).unwrap()
+	}
+
+	#[test]
+	fn same_seed_gives_the_same_statistics_with_any_threads() {
+		let simulation =
+			Simulation { players: 2, ..Simulation::new(Rules::default(), 6) };
+		let statistics = play(&simulation, 1, 7);
+		assert_eq!(statistics.rounds(), 9000);
+		for threads in [2, 3, 8] {
+			assert_eq!(play(&simulation, threads, 7), statistics);
+		}
+		assert_ne!(play(&simulation, 1, 8), statistics);
+	}
+
+	#[test]
+	fn counting_players_are_seeded_the_same() {
+		let simulation = Simulation {
+			counting: Some(CountingPlayer::new(
+				CountingSystem::HI_LO,
+				BetRamp::new(vec![(1.0, 1), (2.0, 4), (4.0, 8)]),
+			)),
+			..Simulation::new(Rules::default(), 2)
+		};
+		let statistics = play(&simulation, 1, 3);
+		assert_eq!(play(&simulation, 4, 3), statistics);
+	}
+}
@@ -0,0 +1,828 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::card::{Card, Rank};
+use crate::deck::Deck;
+use crate::game::{Blackjack, Decision};
+use crate::hand::Hand;
+use crate::rules::{DoubleDownRule, HoleCardRule, Rules, SurrenderRule};
+use crate::score::BlackjackScore;
+
+// A cell of a strategy chart. Doubling down and surrendering are not always
+// allowed, so they come with the decision to fall back to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Play {
+	Hit,
+	Stand,
+	DoubleOrHit,
+	DoubleOrStand,
+	Split,
+	SurrenderOrHit,
+	SurrenderOrStand,
+	SurrenderOrSplit,
+}
+
+impl Play {
+	// Resolves the fallback with `legal` telling which decisions are allowed.
+	pub fn decision(self, legal: &dyn Fn(Decision) -> bool) -> Decision {
+		let (preferred, fallback) = match self {
+			Self::Hit => (Decision::Hit, Decision::Hit),
+			Self::Stand => (Decision::Stand, Decision::Stand),
+			Self::DoubleOrHit => (Decision::DoubleDown, Decision::Hit),
+			Self::DoubleOrStand => (Decision::DoubleDown, Decision::Stand),
+			Self::Split => (Decision::Split, Decision::Hit),
+			Self::SurrenderOrHit => (Decision::Surrender, Decision::Hit),
+			Self::SurrenderOrStand => (Decision::Surrender, Decision::Stand),
+			Self::SurrenderOrSplit => (Decision::Surrender, Decision::Split),
+		};
+
+		if legal(preferred) {
+			preferred
+		} else {
+			fallback
+		}
+	}
+}
+
+impl fmt::Display for Play {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::Hit => f.write_str("H"),
+			Self::Stand => f.write_str("S"),
+			Self::DoubleOrHit => f.write_str("D"),
+			Self::DoubleOrStand => f.write_str("Ds"),
+			Self::Split => f.write_str("P"),
+			Self::SurrenderOrHit => f.write_str("Rh"),
+			Self::SurrenderOrStand => f.write_str("Rs"),
+			Self::SurrenderOrSplit => f.write_str("Rp"),
+		}
+	}
+}
+
+impl FromStr for Play {
+	type Err = ParsePlayError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s.trim().to_ascii_lowercase().as_str() {
+			"h" => Ok(Self::Hit),
+			"s" => Ok(Self::Stand),
+			"d" | "dh" => Ok(Self::DoubleOrHit),
+			"ds" => Ok(Self::DoubleOrStand),
+			"p" => Ok(Self::Split),
+			"rh" => Ok(Self::SurrenderOrHit),
+			"rs" => Ok(Self::SurrenderOrStand),
+			"rp" => Ok(Self::SurrenderOrSplit),
+			_ => Err(ParsePlayError(s.trim().to_string())),
+		}
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ParsePlayError(pub String);
+
+impl fmt::Display for ParsePlayError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"Invalid play `{}`, expected one of H, S, D, Ds, P, Rh, Rs or Rp",
+			self.0
+		)
+	}
+}
+
+impl std::error::Error for ParsePlayError {}
+
+// Hard totals from 4 to 21, soft totals from 12 to 21 and pairs from aces to
+// tens, each against the dealer upcards 2 to 10 and ace.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Chart {
+	hard: [[Play; 10]; 18],
+	soft: [[Play; 10]; 10],
+	pairs: [[Play; 10]; 10],
+}
+
+impl Chart {
+	// The column of the dealer upcard, the ace comes last.
+	const fn column(upcard: &Card) -> usize {
+		match BlackjackScore::value(upcard.rank()) {
+			1 => 9,
+			value => value as usize - 2,
+		}
+	}
+
+	pub fn hard(&self, total: u8, upcard: &Card) -> Play {
+		self.hard[usize::from(total.clamp(4, 21) - 4)][Self::column(upcard)]
+	}
+
+	pub fn soft(&self, total: u8, upcard: &Card) -> Play {
+		self.soft[usize::from(total.clamp(12, 21) - 12)][Self::column(upcard)]
+	}
+
+	pub fn pair(&self, rank: &Rank, upcard: &Card) -> Play {
+		let row = usize::from(BlackjackScore::value(rank) - 1);
+		self.pairs[row][Self::column(upcard)]
+	}
+
+	// The pair row is only used while the hand can still be split, a pair
+	// which can not be split is played by its total.
+	pub fn play(&self, hand: &Hand, upcard: &Card, can_split: bool) -> Play {
+		if let (true, [card, _]) = (can_split && hand.is_pair(), hand.cards())
+		{
+			return self.pair(card.rank(), upcard);
+		}
+
+		match hand.score(&BlackjackScore) {
+			(_, Some(soft)) => self.soft(soft, upcard),
+			(hard, None) => self.hard(hard, upcard),
+		}
+	}
+
+	// The rows in the order of the file format: hard totals, soft totals,
+	// then pairs.
+	fn rows(&self) -> impl Iterator<Item = &[Play; 10]> + '_ {
+		self.hard.iter().chain(&self.soft).chain(&self.pairs)
+	}
+
+	fn rows_mut(&mut self) -> impl Iterator<Item = &mut [Play; 10]> + '_ {
+		self.hard.iter_mut().chain(&mut self.soft).chain(&mut self.pairs)
+	}
+
+	// Rows are labelled `H4` to `H21`, `S12` to `S21` and `AA` to `TT`.
+	fn labels() -> impl Iterator<Item = String> {
+		(4..=21)
+			.map(|total| format!("H{}", total))
+			.chain((12..=21).map(|total| format!("S{}", total)))
+			.chain(PAIRS.iter().map(|pair| pair.to_string()))
+	}
+
+	// The chart as comma separated values, readable by `str::parse`.
+	pub fn to_csv(&self) -> String {
+		let mut csv = String::from(
+			"# hard totals H4-H21, soft totals S12-S21, pairs AA-TT\n",
+		);
+		csv.push_str("upcard");
+		for upcard in &UPCARDS {
+			csv.push(',');
+			csv.push_str(upcard);
+		}
+		csv.push('\n');
+
+		for (label, row) in Self::labels().zip(self.rows()) {
+			csv.push_str(&label);
+			for play in row {
+				csv.push(',');
+				csv.push_str(&play.to_string());
+			}
+			csv.push('\n');
+		}
+
+		csv
+	}
+}
+
+const UPCARDS: [&str; 10] =
+	["2", "3", "4", "5", "6", "7", "8", "9", "10", "A"];
+const PAIRS: [&str; 10] =
+	["AA", "22", "33", "44", "55", "66", "77", "88", "99", "TT"];
+
+// A pretty table for the terminal.
+impl fmt::Display for Chart {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let sections = [("Hard", 0..18), ("Soft", 18..28), ("Pairs", 28..38)];
+		let rows = self.rows().collect::<Vec<_>>();
+
+		for (i, (title, range)) in sections.iter().enumerate() {
+			if i > 0 {
+				writeln!(f)?;
+			}
+
+			write!(f, "{:<6}|", title)?;
+			for upcard in &UPCARDS {
+				write!(f, "{:>3}", upcard)?;
+			}
+			writeln!(f)?;
+			writeln!(f, "{:-<6}+{:-<30}", "", "")?;
+
+			for row in range.clone() {
+				let label = match row {
+					0..=17 => (row + 4).to_string(),
+					18..=27 => match row - 17 {
+						1 => "A,A".to_string(),
+						card => format!("A,{}", card),
+					},
+					_ => match row - 28 {
+						0 => "A,A".to_string(),
+						card => format!("{},{}", card + 1, card + 1),
+					},
+				};
+				write!(f, "{:<6}|", label)?;
+				for play in rows[row] {
+					write!(f, "{:>3}", play.to_string())?;
+				}
+				writeln!(f)?;
+			}
+		}
+
+		Ok(())
+	}
+}
+
+impl FromStr for Chart {
+	type Err = ParseChartError;
+
+	// Blank lines and lines starting with `#` are skipped, the first line is
+	// the header of upcards and every row has to be given exactly once.
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let mut chart = Self {
+			hard: [[Play::Hit; 10]; 18],
+			soft: [[Play::Hit; 10]; 10],
+			pairs: [[Play::Hit; 10]; 10],
+		};
+		let mut seen = [false; 38];
+		let mut header = false;
+
+		let lines = s
+			.lines()
+			.enumerate()
+			.map(|(i, line)| (i + 1, line.trim()))
+			.filter(|(_, line)| !line.is_empty() && !line.starts_with('#'));
+
+		for (line, text) in lines {
+			let mut cells = text.split(',').map(str::trim);
+			let label = cells.next().unwrap_or_default();
+			let cells = cells.collect::<Vec<_>>();
+
+			if !header {
+				let valid = cells.len() == UPCARDS.len()
+					&& cells.iter().zip(&UPCARDS).all(|(cell, upcard)| {
+						cell.eq_ignore_ascii_case(upcard)
+							|| (*upcard == "10"
+								&& cell.eq_ignore_ascii_case("t"))
+					});
+				if !valid {
+					return Err(ParseChartError::InvalidHeader { line });
+				}
+				header = true;
+				continue;
+			}
+
+			let index = Self::labels()
+				.position(|l| l.eq_ignore_ascii_case(label))
+				.ok_or_else(|| ParseChartError::InvalidRow {
+					line,
+					label: label.to_string(),
+				})?;
+			if seen[index] {
+				return Err(ParseChartError::DuplicateRow {
+					line,
+					label: label.to_string(),
+				});
+			}
+			seen[index] = true;
+
+			if cells.len() != UPCARDS.len() {
+				return Err(ParseChartError::InvalidColumns {
+					line,
+					columns: cells.len(),
+				});
+			}
+
+			let row = chart.rows_mut().nth(index).unwrap();
+			for ((play, cell), upcard) in
+				row.iter_mut().zip(cells).zip(&UPCARDS)
+			{
+				*play = cell.parse().map_err(|error| {
+					ParseChartError::InvalidPlay { line, upcard, error }
+				})?;
+			}
+		}
+
+		if !header {
+			return Err(ParseChartError::MissingHeader);
+		}
+		if let Some((label, _)) =
+			Self::labels().zip(&seen).find(|(_, seen)| !**seen)
+		{
+			return Err(ParseChartError::MissingRow(label));
+		}
+
+		Ok(chart)
+	}
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ParseChartError {
+	MissingHeader,
+	InvalidHeader { line: usize },
+	InvalidRow { line: usize, label: String },
+	DuplicateRow { line: usize, label: String },
+	InvalidColumns { line: usize, columns: usize },
+	InvalidPlay { line: usize, upcard: &'static str, error: ParsePlayError },
+	MissingRow(String),
+}
+
+impl fmt::Display for ParseChartError {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			Self::MissingHeader => {
+				f.write_str("Missing the header of upcards")
+			}
+			Self::InvalidHeader { line } => write!(
+				f,
+				"Invalid header at line {}, expected upcards {}",
+				line,
+				UPCARDS.join(",")
+			),
+			Self::InvalidRow { line, label } => write!(
+				f,
+				"Invalid row `{}` at line {}, expected H4-H21, S12-S21 or AA-TT",
+				label, line
+			),
+			Self::DuplicateRow { line, label } => {
+				write!(f, "Duplicate row `{}` at line {}", label, line)
+			}
+			Self::InvalidColumns { line, columns } => write!(
+				f,
+				"Expected {} plays at line {}, found {}",
+				UPCARDS.len(),
+				line,
+				columns
+			),
+			Self::InvalidPlay { line, upcard, error } => write!(
+				f,
+				"Invalid play against {} at line {}: {}",
+				upcard, line, error
+			),
+			Self::MissingRow(label) => write!(f, "Missing row `{}`", label),
+		}
+	}
+}
+
+impl std::error::Error for ParseChartError {
+	fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+		match self {
+			Self::InvalidPlay { error, .. } => Some(error),
+			_ => None,
+		}
+	}
+}
+
+pub trait Strategy {
+	// The decision for the player `hand` against the dealer `upcard`, where
+	// `legal` tells which decisions the game allows right now.
+	fn decide(
+		&self,
+		hand: &Hand,
+		upcard: &Card,
+		rules: &Rules,
+		legal: &dyn Fn(Decision) -> bool,
+	) -> Decision;
+
+	// The decision for a hand which was not split and has every option the
+	// rules offer.
+	fn decision(&self, hand: &Hand, upcard: &Card, rules: &Rules) -> Decision {
+		self.decide(hand, upcard, rules, &|decision| {
+			initially_legal(hand, rules, decision)
+		})
+	}
+}
+
+pub(crate) fn initially_legal(
+	hand: &Hand,
+	rules: &Rules,
+	decision: Decision,
+) -> bool {
+	let initial = hand.cards().len() == 2;
+	let (hard, soft) = hand.score(&BlackjackScore);
+
+	match decision {
+		Decision::Hit | Decision::Stand => true,
+		Decision::DoubleDown => {
+			initial
+				&& match rules.double_down {
+					DoubleDownRule::Any => true,
+					DoubleDownRule::NineToEleven => {
+						soft.is_none() && (9..=11).contains(&hard)
+					}
+					DoubleDownRule::TenToEleven => {
+						soft.is_none() && (10..=11).contains(&hard)
+					}
+				}
+		}
+		Decision::Split => hand.is_pair(),
+		Decision::Surrender => {
+			initial && rules.surrender != SurrenderRule::None
+		}
+	}
+}
+
+// Total dependent basic strategy for the rules of the table and the number of
+// decks in the shoe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BasicStrategy {
+	decks: usize,
+}
+
+impl BasicStrategy {
+	pub const fn new(decks: usize) -> Self {
+		Self { decks }
+	}
+
+	pub const fn decks(&self) -> usize {
+		self.decks
+	}
+
+	pub fn chart(&self, rules: &Rules) -> Chart {
+		let mut chart = Chart {
+			hard: [[Play::Hit; 10]; 18],
+			soft: [[Play::Hit; 10]; 10],
+			pairs: [[Play::Hit; 10]; 10],
+		};
+
+		// upcards are valued 2 to 11 in the order of the columns
+		for (column, up) in (2..=11).enumerate() {
+			for (row, total) in (4..=21).enumerate() {
+				chart.hard[row][column] = self.hard(total, up, rules);
+			}
+			for (row, total) in (12..=21).enumerate() {
+				chart.soft[row][column] = self.soft(total, up, rules);
+			}
+			for (row, card) in (1..=10).enumerate() {
+				chart.pairs[row][column] = self.pair(card, up, rules);
+			}
+		}
+
+		chart
+	}
+
+	// The play of `chart` for a single hand, without building the chart.
+	fn play(
+		&self,
+		hand: &Hand,
+		upcard: &Card,
+		rules: &Rules,
+		can_split: bool,
+	) -> Play {
+		let up = match BlackjackScore::value(upcard.rank()) {
+			1 => 11,
+			value => value,
+		};
+		if let (true, [card, _]) = (can_split && hand.is_pair(), hand.cards())
+		{
+			return self.pair(BlackjackScore::value(card.rank()), up, rules);
+		}
+
+		match hand.score(&BlackjackScore) {
+			(_, Some(soft)) => self.soft(soft.clamp(12, 21), up, rules),
+			(hard, None) => self.hard(hard.clamp(4, 21), up, rules),
+		}
+	}
+
+	// Without a hole card a double or split against a ten or an ace loses
+	// everything to a dealer blackjack.
+	fn all_bets_lost(up: u8, rules: &Rules) -> bool {
+		up >= 10
+			&& rules.hole_card
+				== HoleCardRule::NoHoleCard { original_bets_only: false }
+	}
+
+	// Without a peek surrendering also saves half the bet against a dealer
+	// blackjack.
+	fn early_surrender(rules: &Rules) -> bool {
+		rules.surrender == SurrenderRule::Early
+			|| (rules.surrender == SurrenderRule::Late
+				&& rules.hole_card != HoleCardRule::Peek)
+	}
+
+	fn surrender(&self, total: u8, up: u8, rules: &Rules) -> bool {
+		let h17 = rules.dealer_hits_soft_17;
+		let single_deck = self.decks == 1;
+		let multi_deck = self.decks > 2;
+
+		if Self::early_surrender(rules) {
+			match up {
+				11 => (5..=7).contains(&total) || (12..=17).contains(&total),
+				10 => (14..=16).contains(&total),
+				9 => total == 16,
+				_ => false,
+			}
+		} else if rules.surrender == SurrenderRule::Late {
+			match (total, up) {
+				(16, 10) | (16, 11) => true,
+				(16, 9) => multi_deck,
+				(15, 10) => !single_deck,
+				(15, 11) | (17, 11) => h17 && multi_deck,
+				_ => false,
+			}
+		} else {
+			false
+		}
+	}
+
+	fn hard(&self, total: u8, up: u8, rules: &Rules) -> Play {
+		let h17 = rules.dealer_hits_soft_17;
+		let decks = self.decks;
+
+		let play = match total {
+			17..=21 => Play::Stand,
+			13..=16 if up <= 6 => Play::Stand,
+			12 if (4..=6).contains(&up) => Play::Stand,
+			11 if up <= 10 || h17 || decks <= 2 => Play::DoubleOrHit,
+			10 if up <= 9 => Play::DoubleOrHit,
+			9 if (3..=6).contains(&up) || (up == 2 && decks <= 2) => {
+				Play::DoubleOrHit
+			}
+			8 if (5..=6).contains(&up) && decks == 1 => Play::DoubleOrHit,
+			_ => Play::Hit,
+		};
+
+		let play = match play {
+			Play::DoubleOrHit if Self::all_bets_lost(up, rules) => Play::Hit,
+			play => play,
+		};
+
+		if self.surrender(total, up, rules) {
+			match play {
+				Play::Stand => Play::SurrenderOrStand,
+				_ => Play::SurrenderOrHit,
+			}
+		} else {
+			play
+		}
+	}
+
+	fn soft(&self, total: u8, up: u8, rules: &Rules) -> Play {
+		let h17 = rules.dealer_hits_soft_17;
+		let decks = self.decks;
+
+		let play = match total {
+			20..=21 => Play::Stand,
+			19 if up == 6 && (h17 || decks == 1) => Play::DoubleOrStand,
+			19 => Play::Stand,
+			18 if (3..=6).contains(&up) || (up == 2 && h17) => {
+				Play::DoubleOrStand
+			}
+			18 if up <= 8 || (up == 11 && decks == 1 && !h17) => Play::Stand,
+			17 if (3..=6).contains(&up) || (up == 2 && decks == 1) => {
+				Play::DoubleOrHit
+			}
+			15..=16 if (4..=6).contains(&up) => Play::DoubleOrHit,
+			13..=14 if (5..=6).contains(&up) => Play::DoubleOrHit,
+			_ => Play::Hit,
+		};
+
+		match play {
+			Play::DoubleOrHit if Self::all_bets_lost(up, rules) => Play::Hit,
+			Play::DoubleOrStand if Self::all_bets_lost(up, rules) => {
+				Play::Stand
+			}
+			play => play,
+		}
+	}
+
+	// `card` is the value of each card of the pair with aces counted as 1.
+	fn pair(&self, card: u8, up: u8, rules: &Rules) -> Play {
+		let das = rules.double_after_split;
+
+		let split = match card {
+			1 => !(up == 11 && Self::all_bets_lost(up, rules)),
+			8 => !Self::all_bets_lost(up, rules),
+			9 => (2..=9).contains(&up) && up != 7,
+			7 => up <= 7,
+			6 => (3..=6).contains(&up) || (up == 2 && das),
+			4 => (5..=6).contains(&up) && das,
+			2 | 3 => (4..=7).contains(&up) || (up <= 3 && das),
+			_ => false,
+		};
+
+		if !split {
+			return if card == 1 {
+				self.soft(12, up, rules)
+			} else {
+				self.hard(card * 2, up, rules)
+			};
+		}
+
+		// eights are surrendered against an ace where a sixteen would be,
+		// otherwise splitting is always better
+		if card == 8
+			&& up == 11
+			&& (rules.dealer_hits_soft_17 || Self::early_surrender(rules))
+			&& self.surrender(16, up, rules)
+		{
+			Play::SurrenderOrSplit
+		} else {
+			Play::Split
+		}
+	}
+}
+
+// A chart loaded from a file plays the same whatever the rules.
+impl Strategy for Chart {
+	fn decide(
+		&self,
+		hand: &Hand,
+		upcard: &Card,
+		_rules: &Rules,
+		legal: &dyn Fn(Decision) -> bool,
+	) -> Decision {
+		self.play(hand, upcard, legal(Decision::Split)).decision(legal)
+	}
+}
+
+// An `input` for `Blackjack::run` which plays every hand by `strategy`.
+pub fn input<'s, D: Deck>(
+	strategy: &'s dyn Strategy,
+) -> impl FnMut(usize, usize, &Blackjack<D, BlackjackScore>) -> Decision + 's {
+	move |player, hand, game| {
+		game.advise(strategy, player, hand).unwrap_or(Decision::Stand)
+	}
+}
+
+impl Strategy for BasicStrategy {
+	fn decide(
+		&self,
+		hand: &Hand,
+		upcard: &Card,
+		rules: &Rules,
+		legal: &dyn Fn(Decision) -> bool,
+	) -> Decision {
+		self.play(hand, upcard, rules, legal(Decision::Split)).decision(legal)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use std::iter::FromIterator;
+
+	use super::*;
+	use crate::deck::DECK_52;
+
+	// The play of basic strategy for `hand` against `upcard` with the rule
+	// changes `rules` and `decks` decks.
+	fn play(rules: &str, decks: usize, hand: &str, upcard: &str) -> Play {
+		let rules = rules.parse::<Rules>().unwrap();
+		BasicStrategy::new(decks).chart(&rules).play(
+			&hand.parse().unwrap(),
+			&upcard.parse().unwrap(),
+			true,
+		)
+	}
+
+	#[test]
+	fn decides_like_its_chart() {
+		let ranks = DECK_52.cards().take(13).copied().collect::<Vec<_>>();
+		for (rules, decks) in [("", 6), ("h17,ndas", 1), ("enhc,es", 2)] {
+			let rules = rules.parse::<Rules>().unwrap();
+			let strategy = BasicStrategy::new(decks);
+			let chart = strategy.chart(&rules);
+
+			for first in &ranks {
+				for second in &ranks {
+					let hand = Hand::from_iter(vec![*first, *second]);
+					for (upcard, can_split) in
+						ranks.iter().flat_map(|up| [(up, false), (up, true)])
+					{
+						let legal = |decision| {
+							decision != Decision::Split || can_split
+						};
+						assert_eq!(
+							strategy.decide(&hand, upcard, &rules, &legal),
+							chart.decide(&hand, upcard, &rules, &legal),
+							"{:?} against {}",
+							hand,
+							upcard
+						);
+					}
+				}
+			}
+		}
+	}
+
+	#[test]
+	fn late_surrender_depends_on_the_decks() {
+		for decks in [2, 6] {
+			assert_eq!(play("ls", decks, "Th 5c", "Kd"), Play::SurrenderOrHit);
+		}
+		assert_eq!(play("ls", 1, "Th 5c", "Kd"), Play::Hit);
+		assert_eq!(play("ls", 6, "Th 6c", "9d"), Play::SurrenderOrHit);
+		assert_eq!(play("ls", 2, "Th 6c", "9d"), Play::Hit);
+		assert_eq!(play("ls", 1, "Th 6c", "Kd"), Play::SurrenderOrHit);
+		assert_eq!(play("ns", 6, "Th 6c", "Kd"), Play::Hit);
+	}
+
+	// Cells of the published charts for the rules and decks.
+	#[test]
+	fn matches_published_charts() {
+		let cells = [
+			// stiff hands
+			("", 6, "Th 2c", "2d", Play::Hit),
+			("", 6, "Th 2c", "4d", Play::Stand),
+			("", 6, "Th 3c", "2d", Play::Stand),
+			("", 6, "Th 6c", "7d", Play::Hit),
+			// doubling down on hard totals
+			("", 6, "6h 5c", "Ad", Play::Hit),
+			("h17", 6, "6h 5c", "Ad", Play::DoubleOrHit),
+			("", 1, "6h 5c", "Ad", Play::DoubleOrHit),
+			("", 6, "5h 4c", "2d", Play::Hit),
+			("", 2, "5h 4c", "2d", Play::DoubleOrHit),
+			("", 6, "6h 2c", "6d", Play::Hit),
+			("", 1, "6h 2c", "6d", Play::DoubleOrHit),
+			("enhc", 6, "6h 5c", "Td", Play::Hit),
+			// soft totals
+			("", 6, "Ah 7c", "2d", Play::Stand),
+			("h17", 6, "Ah 7c", "2d", Play::DoubleOrStand),
+			("", 6, "Ah 7c", "9d", Play::Hit),
+			("", 6, "Ah 8c", "6d", Play::Stand),
+			("h17", 6, "Ah 8c", "6d", Play::DoubleOrStand),
+			("", 6, "Ah 2c", "5d", Play::DoubleOrHit),
+			("", 6, "Ah 2c", "4d", Play::Hit),
+			// pairs
+			("", 6, "4h 4c", "5d", Play::Split),
+			("ndas", 6, "4h 4c", "5d", Play::Hit),
+			("", 6, "2h 2c", "2d", Play::Split),
+			("ndas", 6, "2h 2c", "2d", Play::Hit),
+			("", 6, "9h 9c", "7d", Play::Stand),
+			("", 6, "9h 9c", "8d", Play::Split),
+			("", 6, "Th Kc", "6d", Play::Stand),
+			("", 6, "5h 5c", "9d", Play::DoubleOrHit),
+			("", 6, "Ah Ac", "Ad", Play::Split),
+			// surrender
+			("", 6, "Th 6c", "Kd", Play::SurrenderOrHit),
+			("ns", 6, "Th 6c", "Kd", Play::Hit),
+			("h17", 6, "8h 8c", "Ad", Play::SurrenderOrSplit),
+			("", 6, "8h 8c", "Ad", Play::Split),
+			("h17", 6, "Th 7c", "Ad", Play::SurrenderOrStand),
+		];
+
+		for (rules, decks, hand, upcard, expected) in cells {
+			assert_eq!(
+				play(rules, decks, hand, upcard),
+				expected,
+				"{} against {} with {} and {} decks",
+				hand,
+				upcard,
+				rules,
+				decks
+			);
+		}
+	}
+
+	#[test]
+	fn charts_round_trip_as_csv() {
+		for (rules, decks) in [("", 6), ("h17,ndas,ns", 1), ("enhc,es", 2)] {
+			let rules = rules.parse::<Rules>().unwrap();
+			let chart = BasicStrategy::new(decks).chart(&rules);
+			assert_eq!(chart.to_csv().parse::<Chart>(), Ok(chart));
+		}
+	}
+
+	// The CSV of the default chart with `edit` applied to its lines, the
+	// comment being line 1 and the header line 2.
+	fn parse(
+		edit: impl FnOnce(&mut Vec<String>),
+	) -> Result<Chart, ParseChartError> {
+		let csv = BasicStrategy::new(6).chart(&Rules::default()).to_csv();
+		let mut lines = csv.lines().map(String::from).collect::<Vec<_>>();
+		edit(&mut lines);
+		lines.join("\n").parse()
+	}
+
+	#[test]
+	fn reports_what_is_wrong_with_a_chart() {
+		assert_eq!(
+			"# nothing\n".parse::<Chart>(),
+			Err(ParseChartError::MissingHeader)
+		);
+		assert_eq!(
+			parse(|lines| lines[1] = "upcard,2,3,4,5,6,7,8,9,A,10".into()),
+			Err(ParseChartError::InvalidHeader { line: 2 })
+		);
+		assert_eq!(
+			parse(|lines| lines[2] = lines[2].replacen("H4", "H3", 1)),
+			Err(ParseChartError::InvalidRow { line: 3, label: "H3".into() })
+		);
+		assert_eq!(
+			parse(|lines| lines[3] = lines[2].clone()),
+			Err(ParseChartError::DuplicateRow { line: 4, label: "H4".into() })
+		);
+		assert_eq!(
+			parse(|lines| lines[2].push_str(",H")),
+			Err(ParseChartError::InvalidColumns { line: 3, columns: 11 })
+		);
+		assert_eq!(
+			parse(|lines| lines[2] = "H4,H,H,H,X,H,H,H,H,H,H".into()),
+			Err(ParseChartError::InvalidPlay {
+				line: 3,
+				upcard: "5",
+				error: ParsePlayError("X".into())
+			})
+		);
+		assert_eq!(
+			parse(|lines| {
+				lines.remove(20);
+			}),
+			Err(ParseChartError::MissingRow("S12".into()))
+		);
+	}
+}
@@ -0,0 +1,285 @@
+use rand::rngs::ThreadRng;
+use rand::{thread_rng, RngCore};
+
+use crate::card::Card;
+use crate::deck::Deck;
+use crate::error::BlackjackError;
+use crate::event::{DealerEvent, Event};
+use crate::game::{Blackjack, Decision, Wager};
+use crate::rules::Rules;
+use crate::score::BlackjackScore;
+use crate::strategy::Chart;
+
+// Plays round after round with the same deck. Players can join and leave the
+// seats between rounds and cards of finished rounds go to the discard tray
+// until the deck is shuffled.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Table<D, R = ThreadRng> {
+	deck: D,
+	rng: R,
+	rules: Rules,
+	// bankroll of the player on each seat
+	seats: Vec<Option<u64>>,
+	discard: Vec<Card>,
+	rounds: usize,
+	coach: Option<Chart>,
+}
+
+impl<D> Table<D>
+where
+	D: Deck,
+{
+	pub fn new(seats: usize, deck: D, rules: Rules) -> Self {
+		Self::with_rng(seats, deck, rules, thread_rng())
+	}
+}
+
+impl<D, R> Table<D, R>
+where
+	D: Deck,
+	R: RngCore,
+{
+	// Shuffles the deck with `rng`, a seeded generator replays the exact
+	// same cards.
+	pub fn with_rng(seats: usize, deck: D, rules: Rules, rng: R) -> Self {
+		Self {
+			deck,
+			rng,
+			rules,
+			seats: vec![None; seats],
+			discard: Vec::new(),
+			rounds: 0,
+			coach: None,
+		}
+	}
+
+	pub const fn rules(&self) -> &Rules {
+		&self.rules
+	}
+
+	pub const fn coach(&self) -> Option<&Chart> {
+		self.coach.as_ref()
+	}
+
+	// Coaches the players in every round, see `Blackjack::set_coach`.
+	pub const fn set_coach(&mut self, coach: Option<Chart>) {
+		self.coach = coach;
+	}
+
+	pub const fn deck(&self) -> &D {
+		&self.deck
+	}
+
+	pub fn discard(&self) -> &[Card] {
+		&self.discard
+	}
+
+	pub const fn rounds(&self) -> usize {
+		self.rounds
+	}
+
+	pub const fn seats(&self) -> usize {
+		self.seats.len()
+	}
+
+	pub fn bankroll(&self, seat: usize) -> Option<u64> {
+		self.seats.get(seat).copied().flatten()
+	}
+
+	pub fn players(&self) -> impl Iterator<Item = usize> + '_ {
+		self.seats
+			.iter()
+			.enumerate()
+			.filter_map(|(seat, bankroll)| bankroll.map(|_| seat))
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.players().next().is_none()
+	}
+
+	// Seats a player on the first free seat and returns it.
+	pub fn join(&mut self, bankroll: u64) -> Option<usize> {
+		let seat = self.seats.iter().position(Option::is_none)?;
+		self.seats[seat] = Some(bankroll);
+		Some(seat)
+	}
+
+	pub fn join_at(
+		&mut self,
+		seat: usize,
+		bankroll: u64,
+	) -> Result<(), BlackjackError> {
+		match self.seats.get_mut(seat) {
+			Some(free @ None) => {
+				*free = Some(bankroll);
+				Ok(())
+			}
+			Some(Some(_)) => Err(BlackjackError::SeatTaken(seat)),
+			None => Err(BlackjackError::InvalidSeat(seat)),
+		}
+	}
+
+	// Removes the player from the seat and returns the remaining bankroll.
+	pub fn leave(&mut self, seat: usize) -> Result<u64, BlackjackError> {
+		match self.seats.get_mut(seat) {
+			Some(player) => {
+				player.take().ok_or(BlackjackError::EmptySeat(seat))
+			}
+			None => Err(BlackjackError::InvalidSeat(seat)),
+		}
+	}
+
+	// Plays a single round with the seated players. Players without any
+	// bankroll left have to leave the table afterwards.
+	pub fn round<W, I, E>(
+		&mut self,
+		wager: W,
+		input: I,
+		mut event: E,
+	) -> Result<(), BlackjackError>
+	where
+		W: FnMut(usize, Wager, &Blackjack<&mut D, BlackjackScore>) -> u64,
+		I: FnMut(usize, usize, &Blackjack<&mut D, BlackjackScore>) -> Decision,
+		E: for<'a> FnMut(Event<'a>),
+	{
+		// the discards go back into the deck before it is shuffled, a deck
+		// which can not take them back is only shuffled by the game
+		if self.deck.needs_shuffle() && self.deck.reshuffle(&[], &mut self.rng)
+		{
+			self.discard.clear();
+		}
+
+		let bankrolls = self
+			.seats
+			.iter()
+			.map(|seat| seat.unwrap_or(0))
+			.collect::<Vec<_>>();
+		let mut game = Blackjack::with_bankrolls(
+			&bankrolls,
+			&mut self.deck,
+			self.rules,
+			&mut self.rng,
+		);
+		game.set_coach(self.coach.clone());
+		// a deck which ran out took back the discards
+		let mut shuffled = false;
+		let game = game.run(wager, input, |e: Event<'_>| {
+			shuffled |=
+				matches!(e, Event::Dealer(DealerEvent::Shuffle { .. }));
+			event(e)
+		})?;
+
+		if shuffled {
+			self.discard.clear();
+		}
+		self.discard.extend(game.cards());
+		for (player, seat) in self.seats.iter_mut().enumerate() {
+			if seat.is_some() {
+				*seat = game.bankroll(player).filter(|&bankroll| bankroll > 0);
+			}
+		}
+
+		self.rounds += 1;
+		Ok(())
+	}
+
+	// Plays rounds until `next` ends the session or no player is left at the
+	// table. `next` is called between rounds and may seat or remove players.
+	pub fn run<W, I, E, N>(
+		&mut self,
+		mut wager: W,
+		mut input: I,
+		mut event: E,
+		mut next: N,
+	) -> Result<(), BlackjackError>
+	where
+		W: FnMut(usize, Wager, &Blackjack<&mut D, BlackjackScore>) -> u64,
+		I: FnMut(usize, usize, &Blackjack<&mut D, BlackjackScore>) -> Decision,
+		E: for<'a> FnMut(Event<'a>),
+		N: FnMut(&mut Self) -> bool,
+	{
+		while !self.is_empty() {
+			self.round(&mut wager, &mut input, &mut event)?;
+
+			if self.is_empty() || !next(self) {
+				break;
+			}
+		}
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use rand::rngs::StdRng;
+	use rand::SeedableRng;
+
+	use super::*;
+	use crate::deck::{Deck52, DECK_52};
+
+	fn table(seats: usize) -> Table<Deck52, StdRng> {
+		Table::with_rng(
+			seats,
+			DECK_52,
+			Rules::default(),
+			StdRng::seed_from_u64(7),
+		)
+	}
+
+	#[test]
+	fn players_join_and_leave_seats() {
+		let mut table = table(3);
+		assert!(table.is_empty());
+		assert_eq!(table.join(100), Some(0));
+		assert_eq!(table.join_at(2, 50), Ok(()));
+		assert_eq!(table.join_at(2, 50), Err(BlackjackError::SeatTaken(2)));
+		assert_eq!(table.join_at(3, 50), Err(BlackjackError::InvalidSeat(3)));
+		assert_eq!(table.join(25), Some(1));
+		assert_eq!(table.join(25), None);
+
+		assert_eq!(table.leave(1), Ok(25));
+		assert_eq!(table.leave(1), Err(BlackjackError::EmptySeat(1)));
+		assert_eq!(table.leave(3), Err(BlackjackError::InvalidSeat(3)));
+		assert_eq!(table.players().collect::<Vec<_>>(), [0, 2]);
+		assert_eq!(table.bankroll(2), Some(50));
+		assert_eq!(table.bankroll(1), None);
+	}
+
+	// The deck is dealt down over the rounds, the cards of each round go to
+	// the discard tray until the deck is shuffled.
+	#[test]
+	fn discards_collect_until_the_deck_is_shuffled() {
+		let mut table = table(2);
+		table.join(1_000_000);
+		table.join(1_000_000);
+
+		let mut discards = Vec::new();
+		let mut shuffles = 0;
+		for round in 1..=40 {
+			table
+				.round(
+					|_, wager, _| if wager == Wager::Bet { 10 } else { 0 },
+					|_, _, _| Decision::Stand,
+					|event| {
+						if let Event::Dealer(DealerEvent::Shuffle { .. }) =
+							event
+						{
+							shuffles += 1;
+						}
+					},
+				)
+				.unwrap();
+			assert_eq!(table.rounds(), round);
+			assert_eq!(table.deck().len() + table.discard().len(), 52);
+			discards.push(table.discard().len());
+		}
+
+		assert!(discards[1] > discards[0]);
+		// the tray is emptied when the deck runs out, between rounds or in
+		// the middle of one
+		let emptied = discards.windows(2).filter(|len| len[1] < len[0]);
+		assert!(emptied.count() >= 5);
+		assert!(shuffles > 0);
+	}
+}
@@ -0,0 +1,467 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::Duration;
+
+use rand::rngs::ThreadRng;
+use rand::{thread_rng, Rng, RngCore};
+
+use crate::card::Card;
+use crate::counting::{Counter, CountingSystem, Row};
+use crate::deck::{Deck, Shoe};
+use crate::game::Decision;
+use crate::hand::Hand;
+use crate::rules::Rules;
+use crate::score::BlackjackScore;
+use crate::strategy::{BasicStrategy, Chart, Strategy};
+
+// Answers given in a drill, how many of them were right and how long they
+// took.
+#[derive(
+	Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+pub struct Tally {
+	answers: u64,
+	correct: u64,
+	time: Duration,
+}
+
+impl Tally {
+	pub const fn new() -> Self {
+		Self { answers: 0, correct: 0, time: Duration::ZERO }
+	}
+
+	pub fn record(&mut self, correct: bool, time: Duration) {
+		self.answers += 1;
+		self.correct += u64::from(correct);
+		self.time += time;
+	}
+
+	pub fn merge(&mut self, other: &Self) {
+		self.answers += other.answers;
+		self.correct += other.correct;
+		self.time += other.time;
+	}
+
+	pub const fn answers(&self) -> u64 {
+		self.answers
+	}
+
+	pub const fn correct(&self) -> u64 {
+		self.correct
+	}
+
+	pub const fn mistakes(&self) -> u64 {
+		self.answers - self.correct
+	}
+
+	pub fn accuracy(&self) -> f64 {
+		if self.answers == 0 {
+			return 0.0;
+		}
+		self.correct as f64 / self.answers as f64
+	}
+
+	pub fn average_time(&self) -> Duration {
+		if self.answers == 0 {
+			return Duration::ZERO;
+		}
+		self.time / self.answers as u32
+	}
+}
+
+impl fmt::Display for Tally {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(
+			f,
+			"{}/{} correct ({:.1}%), {:.1}s per answer",
+			self.correct,
+			self.answers,
+			self.accuracy() * 100.0,
+			self.average_time().as_secs_f64()
+		)
+	}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CountQuestion {
+	RunningCount,
+	// Answers count when they are the true count rounded to a whole number.
+	TrueCount,
+}
+
+impl fmt::Display for CountQuestion {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		f.write_str(match self {
+			Self::RunningCount => "running count",
+			Self::TrueCount => "true count",
+		})
+	}
+}
+
+// Deals the cards of a shoe one by one for the player to count and checks
+// the counts they give.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CountingDrill<R = ThreadRng> {
+	shoe: Shoe,
+	rng: R,
+	counter: Counter,
+	running_count: Tally,
+	true_count: Tally,
+}
+
+impl CountingDrill {
+	pub fn new(
+		system: CountingSystem,
+		decks: usize,
+		penetration: f64,
+	) -> Self {
+		Self::with_rng(system, decks, penetration, thread_rng())
+	}
+}
+
+impl<R> CountingDrill<R>
+where
+	R: RngCore,
+{
+	pub fn with_rng(
+		system: CountingSystem,
+		decks: usize,
+		penetration: f64,
+		mut rng: R,
+	) -> Self {
+		let mut shoe = Shoe::new(decks, penetration);
+		shoe.shuffle_with(&mut rng);
+
+		Self {
+			shoe,
+			rng,
+			counter: Counter::new(system, decks),
+			running_count: Tally::new(),
+			true_count: Tally::new(),
+		}
+	}
+
+	pub const fn shoe(&self) -> &Shoe {
+		&self.shoe
+	}
+
+	pub const fn counter(&self) -> &Counter {
+		&self.counter
+	}
+
+	// Deals the next card and counts it. At the cut card the shoe is
+	// shuffled and the count starts over.
+	pub fn deal(&mut self) -> Option<Card> {
+		if self.shoe.needs_shuffle() {
+			self.shoe.shuffle_with(&mut self.rng);
+			self.counter.shuffle();
+		}
+
+		let card = self.shoe.draw()?;
+		self.counter.count(&card);
+		Some(card)
+	}
+
+	// The answer to `question` for the cards dealt so far.
+	pub fn expected(&self, question: CountQuestion) -> f64 {
+		match question {
+			CountQuestion::RunningCount => self.counter.running_count(),
+			// without a negative zero
+			CountQuestion::TrueCount => {
+				self.counter.true_count().round() + 0.0
+			}
+		}
+	}
+
+	// Records an answer which took `time` and tells whether it was right.
+	pub fn answer(
+		&mut self,
+		question: CountQuestion,
+		answer: f64,
+		time: Duration,
+	) -> bool {
+		let correct = (answer - self.expected(question)).abs() < 1e-9;
+		match question {
+			CountQuestion::RunningCount => &mut self.running_count,
+			CountQuestion::TrueCount => &mut self.true_count,
+		}
+		.record(correct, time);
+		correct
+	}
+
+	pub const fn tally(&self, question: CountQuestion) -> &Tally {
+		match question {
+			CountQuestion::RunningCount => &self.running_count,
+			CountQuestion::TrueCount => &self.true_count,
+		}
+	}
+}
+
+// A starting hand against the dealer upcard.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Position {
+	pub hand: Hand,
+	pub upcard: Card,
+}
+
+impl Position {
+	pub fn row(&self) -> Row {
+		Row::new(&self.hand, true)
+	}
+}
+
+// Chance of asking again for a position which was decided wrong.
+const REDRILL: f64 = 1.0 / 3.0;
+
+// Deals positions from a shoe for the player to decide and grades the
+// decisions by a strategy chart. Positions decided wrong come back until
+// they are decided right.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StrategyDrill<R = ThreadRng> {
+	shoe: Shoe,
+	rng: R,
+	rules: Rules,
+	chart: Chart,
+	missed: Vec<Position>,
+	tallies: BTreeMap<Row, Tally>,
+}
+
+impl StrategyDrill {
+	// Drills the basic strategy of the rules.
+	pub fn new(rules: Rules, decks: usize) -> Self {
+		let chart = BasicStrategy::new(decks).chart(&rules);
+		Self::with_rng(rules, chart, decks, thread_rng())
+	}
+}
+
+impl<R> StrategyDrill<R>
+where
+	R: RngCore,
+{
+	pub fn with_rng(
+		rules: Rules,
+		chart: Chart,
+		decks: usize,
+		mut rng: R,
+	) -> Self {
+		let mut shoe = Shoe::new(decks, 0.75);
+		shoe.shuffle_with(&mut rng);
+
+		Self {
+			shoe,
+			rng,
+			rules,
+			chart,
+			missed: Vec::new(),
+			tallies: BTreeMap::new(),
+		}
+	}
+
+	pub const fn rules(&self) -> &Rules {
+		&self.rules
+	}
+
+	pub const fn chart(&self) -> &Chart {
+		&self.chart
+	}
+
+	// Positions decided wrong and not yet decided right since.
+	pub fn missed(&self) -> &[Position] {
+		&self.missed
+	}
+
+	// The next position, either dealt from the shoe or one decided wrong
+	// before. Naturals need no decision and are never dealt.
+	pub fn position(&mut self) -> Option<Position> {
+		if !self.missed.is_empty() && self.rng.gen_bool(REDRILL) {
+			let missed = self.rng.gen_range(0..self.missed.len());
+			return Some(self.missed[missed].clone());
+		}
+
+		loop {
+			if self.shoe.needs_shuffle() {
+				self.shoe.shuffle_with(&mut self.rng);
+			}
+
+			let hand = [self.shoe.draw()?, self.shoe.draw()?]
+				.iter()
+				.copied()
+				.collect::<Hand>();
+			let upcard = self.shoe.draw()?;
+			if hand.score(&BlackjackScore) != (11, Some(21)) {
+				return Some(Position { hand, upcard });
+			}
+		}
+	}
+
+	pub fn correct(&self, position: &Position) -> Decision {
+		self.chart.decision(&position.hand, &position.upcard, &self.rules)
+	}
+
+	// Records a decision which took `time` and tells whether it was right.
+	pub fn answer(
+		&mut self,
+		position: &Position,
+		decision: Decision,
+		time: Duration,
+	) -> bool {
+		let correct = decision == self.correct(position);
+		self.tallies.entry(position.row()).or_default().record(correct, time);
+
+		if correct {
+			self.missed.retain(|missed| missed != position);
+		} else if !self.missed.contains(position) {
+			self.missed.push(position.clone());
+		}
+		correct
+	}
+
+	// The answers for each row of the chart.
+	pub fn tallies(&self) -> impl Iterator<Item = (&Row, &Tally)> + '_ {
+		self.tallies.iter()
+	}
+
+	// The answers for all rows together.
+	pub fn total(&self) -> Tally {
+		let mut total = Tally::new();
+		for tally in self.tallies.values() {
+			total.merge(tally);
+		}
+		total
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use rand::rngs::StdRng;
+	use rand::SeedableRng;
+
+	use super::*;
+
+	const fn secs(secs: u64) -> Duration {
+		Duration::from_secs(secs)
+	}
+
+	#[test]
+	fn tallies_sum_up_the_answers() {
+		let mut tally = Tally::new();
+		assert_eq!(tally.accuracy(), 0.0);
+		assert_eq!(tally.average_time(), Duration::ZERO);
+
+		tally.record(true, secs(1));
+		tally.record(false, secs(4));
+		tally.record(true, secs(1));
+		assert_eq!(tally.answers(), 3);
+		assert_eq!(tally.correct(), 2);
+		assert_eq!(tally.mistakes(), 1);
+		assert_eq!(tally.average_time(), secs(2));
+		assert_eq!(tally.to_string(), "2/3 correct (66.7%), 2.0s per answer");
+
+		let mut total = Tally::new();
+		total.record(false, secs(2));
+		total.merge(&tally);
+		assert_eq!(total.answers(), 4);
+		assert_eq!(total.accuracy(), 0.5);
+		assert_eq!(total.average_time(), secs(2));
+	}
+
+	// Hi-Lo counts the cards dealt from two decks, the drill starts over at
+	// the cut card.
+	#[test]
+	fn counting_drill_checks_the_counts() {
+		let mut drill = CountingDrill::with_rng(
+			CountingSystem::HI_LO,
+			2,
+			0.5,
+			StdRng::seed_from_u64(4),
+		);
+		let mut running = 0.0;
+		for _ in 0..26 {
+			let card = drill.deal().unwrap();
+			running += match BlackjackScore::value(card.rank()) {
+				2..=6 => 1.0,
+				7..=9 => 0.0,
+				_ => -1.0,
+			};
+		}
+
+		let question = CountQuestion::RunningCount;
+		assert_eq!(drill.expected(question), running);
+		assert!(drill.answer(question, running, secs(1)));
+		assert!(!drill.answer(question, running + 1.0, secs(3)));
+		assert_eq!(drill.tally(question).correct(), 1);
+		assert_eq!(drill.tally(question).mistakes(), 1);
+
+		// 78 cards left are one and a half decks
+		let question = CountQuestion::TrueCount;
+		let true_count = (running / 1.5).round() + 0.0;
+		assert_eq!(drill.expected(question), true_count);
+		assert!(drill.answer(question, true_count, secs(2)));
+		assert_eq!(drill.tally(question).answers(), 1);
+		assert_eq!(drill.tally(CountQuestion::RunningCount).answers(), 2);
+
+		for _ in 26..52 {
+			drill.deal().unwrap();
+		}
+		assert!(drill.shoe().cut_card_reached());
+		let card = drill.deal().unwrap();
+		assert_eq!(drill.shoe().dealt(), 1);
+		assert_eq!(
+			drill.expected(CountQuestion::RunningCount),
+			CountingSystem::HI_LO.tag(&card)
+		);
+	}
+
+	#[test]
+	fn strategy_drill_deals_no_naturals() {
+		let mut drill = StrategyDrill::with_rng(
+			Rules::default(),
+			BasicStrategy::new(6).chart(&Rules::default()),
+			6,
+			StdRng::seed_from_u64(5),
+		);
+		for _ in 0..1000 {
+			let position = drill.position().unwrap();
+			assert_eq!(position.hand.cards().len(), 2);
+			assert_ne!(position.hand.score(&BlackjackScore), (11, Some(21)));
+		}
+	}
+
+	// Hard 17 against a 6 is a stand, a hit is a mistake the drill asks for
+	// again about a third of the time until it is answered right.
+	#[test]
+	fn strategy_drill_redrills_missed_positions() {
+		let rules = Rules::default();
+		let mut drill = StrategyDrill::with_rng(
+			rules,
+			BasicStrategy::new(6).chart(&rules),
+			6,
+			StdRng::seed_from_u64(6),
+		);
+		let position = Position {
+			hand: "Th 7c".parse().unwrap(),
+			upcard: Card::SPADE_SIX,
+		};
+		assert_eq!(drill.correct(&position), Decision::Stand);
+
+		assert!(!drill.answer(&position, Decision::Hit, secs(1)));
+		assert!(!drill.answer(&position, Decision::Hit, secs(1)));
+		assert_eq!(drill.missed(), std::slice::from_ref(&position));
+
+		let redrilled = (0..3000)
+			.filter(|_| drill.position().unwrap() == position)
+			.count();
+		assert!((900..1100).contains(&redrilled), "{}", redrilled);
+
+		assert!(drill.answer(&position, Decision::Stand, secs(1)));
+		assert!(drill.missed().is_empty());
+
+		let row = position.row();
+		let tallies = drill.tallies().collect::<Vec<_>>();
+		assert_eq!(tallies.len(), 1);
+		assert_eq!(*tallies[0].0, row);
+		assert_eq!(tallies[0].1.mistakes(), 2);
+		assert_eq!(drill.total().answers(), 3);
+		assert_eq!(drill.total().correct(), 1);
+	}
+}